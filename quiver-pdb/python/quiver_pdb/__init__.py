@@ -1,21 +1,61 @@
 from .quiver_pdb import (
     extract_pdbs,
+    extract_multimodel_pdb,
+    extract_mmcif,
+    extract_tar,
+    to_arrow,
     qvfrompdbs,
     extract_scorefile,
+    qvstats,
+    qvdssp,
+    qvinterface,
+    rmsd_to_reference,
+    rmsd_matrix,
+    qvcluster,
+    qvselect,
+    qvtransform,
     list_tags,
     rename_tags,
     qvslice,
     qvsplit,
+    qvcat,
+    qvverify,
+    qvrepair,
+    qvsample,
+    qvsort,
+    qvdedup,
+    qvdiff,
     Quiver,
+    QuiverWriter,
 )
 
 __all__ = [
     'extract_pdbs',
+    'extract_multimodel_pdb',
+    'extract_mmcif',
+    'extract_tar',
+    'to_arrow',
     'qvfrompdbs',
     'extract_scorefile',
+    'qvstats',
+    'qvdssp',
+    'qvinterface',
+    'rmsd_to_reference',
+    'rmsd_matrix',
+    'qvcluster',
+    'qvselect',
+    'qvtransform',
     'list_tags',
     'rename_tags',
     'qvslice',
     'qvsplit',
+    'qvcat',
+    'qvverify',
+    'qvrepair',
+    'qvsample',
+    'qvsort',
+    'qvdedup',
+    'qvdiff',
     'Quiver',
+    'QuiverWriter',
 ]
@@ -0,0 +1,29 @@
+#!/usr/bin/env python3
+"""
+Merge several Quiver (.qv) files into one, keeping the file header.
+
+Usage:
+    qvcat.py merged.qv run1.qv run2.qv run3.qv
+"""
+
+import sys
+import click
+from quiver_pdb import qvcat as rust_qvcat  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.argument("quiver_files", nargs=-1, required=True, type=click.Path(exists=True, dir_okay=False))
+def qvcat(output, quiver_files):
+    """
+    Concatenate QUIVER_FILES into OUTPUT. Tags must be unique across inputs.
+    """
+    try:
+        rust_qvcat(list(quiver_files), output)
+    except Exception as e:
+        click.secho(f"Error merging Quiver files: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvcat()
@@ -0,0 +1,52 @@
+#!/usr/bin/env python3
+"""
+Cluster the records of a Quiver (.qv) file by CA-RMSD or TM-score and pick
+one representative per cluster.
+
+Usage:
+    qvcluster.py mydesigns.qv --threshold 2.0
+    qvcluster.py mydesigns.qv --metric tmscore --threshold 0.6 --output reps.qv
+    qvcluster.py mydesigns.qv --threshold 1.5 --score-term ddg --table clusters.tsv
+"""
+
+import sys
+import click
+from quiver_pdb import qvcluster as rust_qvcluster  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.option("--threshold", type=float, required=True,
+              help="Maximum CA-RMSD (Å), or minimum TM-score, within a cluster")
+@click.option("--metric", type=click.Choice(["rmsd", "tmscore"]), default="rmsd", show_default=True)
+@click.option("--method", type=click.Choice(["leader", "greedy"]), default="leader", show_default=True,
+              help="leader: compare to representatives only; greedy: compare all pairs")
+@click.option("--score-term", default=None,
+              help="Consider records from the lowest value of this score term up")
+@click.option("--output", type=click.Path(dir_okay=False, writable=True), default=None,
+              help="Write the representatives to this Quiver file")
+@click.option("--table", type=click.Path(dir_okay=False, writable=True), default=None,
+              help="Write tag, cluster and representative columns to this TSV file")
+def qvcluster(quiver_file, threshold, metric, method, score_term, output, table):
+    """
+    Cluster the structures in QUIVER_FILE.
+    """
+    try:
+        result = rust_qvcluster(
+            quiver_file, threshold, metric=metric, method=method,
+            score_term=score_term, output=output,
+        )
+    except Exception as e:
+        click.secho(f"Error clustering Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+    if table:
+        reps = result["representatives"]
+        with open(table, "w") as f:
+            f.write("tag\tcluster\trepresentative\n")
+            for tag, cluster in zip(result["tags"], result["cluster"]):
+                f.write(f"{tag}\t{cluster}\t{reps[cluster]}\n")
+
+
+if __name__ == "__main__":
+    qvcluster()
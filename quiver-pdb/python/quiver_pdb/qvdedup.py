@@ -0,0 +1,35 @@
+#!/usr/bin/env python3
+"""
+Remove duplicate records from a Quiver (.qv) file.
+
+Usage:
+    qvdedup.py mydesigns.qv unique.qv
+    qvdedup.py mydesigns.qv unique.qv --by-structure
+"""
+
+import sys
+import click
+from quiver_pdb import qvdedup as rust_qvdedup  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.option(
+    "--by-structure",
+    is_flag=True,
+    help="Also drop records whose coordinates duplicate an earlier record",
+)
+def qvdedup(quiver_file, output, by_structure):
+    """
+    Write QUIVER_FILE to OUTPUT keeping only the first record of each tag.
+    """
+    try:
+        rust_qvdedup(quiver_file, output, by_structure=by_structure)
+    except Exception as e:
+        click.secho(f"Error deduplicating Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvdedup()
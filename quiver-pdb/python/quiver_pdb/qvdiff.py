@@ -0,0 +1,43 @@
+#!/usr/bin/env python3
+"""
+Compare two Quiver (.qv) files record by record.
+
+Reports tags found in only one file, tags whose structure differs and score
+terms whose values differ. Exits with status 1 when the files differ, like
+diff(1).
+
+Usage:
+    qvdiff.py run1.qv run2.qv
+    qvdiff.py original.qv converted.qv --rmsd-tolerance 0.001
+    qvdiff.py original.qv rescored.qv --no-scores
+"""
+
+import sys
+import click
+from quiver_pdb import qvdiff as rust_qvdiff  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_a", type=click.Path(exists=True, dir_okay=False))
+@click.argument("quiver_b", type=click.Path(exists=True, dir_okay=False))
+@click.option(
+    "--rmsd-tolerance",
+    type=float,
+    default=None,
+    help="Ignore structure text changes whose coordinate RMSD is at most this (Å)",
+)
+@click.option("--scores/--no-scores", default=True, help="Compare QV_SCORE terms (default: on)")
+def qvdiff(quiver_a, quiver_b, rmsd_tolerance, scores):
+    """
+    Compare QUIVER_A against QUIVER_B.
+    """
+    try:
+        result = rust_qvdiff(quiver_a, quiver_b, rmsd_tolerance=rmsd_tolerance, scores=scores)
+    except Exception as e:
+        click.secho(f"Error comparing Quiver files: {e}", fg="red", err=True)
+        sys.exit(2)
+    sys.exit(0 if result["identical"] else 1)
+
+
+if __name__ == "__main__":
+    qvdiff()
@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""
+This script assigns DSSP-style secondary structure (H, G, I, E, B, T, S and
+- for loop) to every record of a Quiver (.qv) file and reports the
+helix, strand and loop fractions.
+
+Usage:
+    qvdssp.py mydesigns.qv
+    qvdssp.py mydesigns.qv --output dssp.csv
+    qvdssp.py mydesigns.qv --append-scores
+"""
+
+import sys
+import click
+from quiver_pdb import qvdssp
+
+@click.command()
+@click.argument("qvfile", type=click.Path(exists=True, dir_okay=False))
+@click.option(
+    "--output",
+    type=click.Path(dir_okay=False, writable=True),
+    default=None,
+    help="Output path (default: next to QVFILE as .dssp.sc)",
+)
+@click.option(
+    "--format",
+    "fmt",
+    type=click.Choice(["tsv", "csv", "jsonl", "parquet"]),
+    default=None,
+    help="Output format (default: from the --output extension, else tsv)",
+)
+@click.option(
+    "--append-scores",
+    is_flag=True,
+    help="Add the assignment as QV_SCORE terms in QVFILE instead of writing a table",
+)
+def main(qvfile, output, fmt, append_scores):
+    """
+    Assigns secondary structure to every record in the Quiver file.
+    """
+    try:
+        qvdssp(qvfile, output=output, format=fmt, append_scores=append_scores)
+    except Exception as e:
+        click.secho(f"❌ Error: {str(e)}", fg="red", err=True)
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
@@ -4,18 +4,63 @@ This is a command-line tool to extract all PDB files from a Quiver file.
 
 Usage:
     qvextract.py <quiver_file>
+    qvextract.py <quiver_file> --format multimodel --output models.pdb
+    qvextract.py <quiver_file> --format cif --output cif_dir/
+    qvextract.py <quiver_file> --format tar --output designs.tar.gz
+    qvextract.py <quiver_file> --format parquet --output designs.parquet
+    qvextract.py <quiver_file> --select "chain A"
 """
 
+import sys
 import click
-from quiver_pdb import extract_pdbs
+from quiver_pdb import extract_pdbs, extract_multimodel_pdb, extract_mmcif, extract_tar, to_arrow
 
 @click.command()
 @click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
-def main(quiver_file):
+@click.option(
+    "--format",
+    "fmt",
+    type=click.Choice(["pdb", "multimodel", "cif", "tar", "parquet"]),
+    default="pdb",
+    help="pdb: one file per tag (default), multimodel: a single multi-MODEL PDB, "
+    "cif: one mmCIF file per tag, tar: a .tar/.tar.gz of PDB files, "
+    "parquet: one row per tag with per-atom list columns",
+)
+@click.option(
+    "--output",
+    type=click.Path(),
+    default=None,
+    help="Output file (multimodel, tar, parquet) or directory (cif)",
+)
+@click.option(
+    "--select",
+    default=None,
+    help='Only write atoms matching a selection, e.g. "chain A and not hydrogen"',
+)
+def main(quiver_file, fmt, output, select):
     """
     Extract all PDB files from a Quiver file.
     """
-    extract_pdbs(quiver_file)
+    if fmt == "pdb":
+        extract_pdbs(quiver_file, select=select)
+        return
+
+    if output is None:
+        click.secho(f"❌ --output is required for --format {fmt}", fg="red", err=True)
+        sys.exit(1)
+
+    try:
+        if fmt == "multimodel":
+            extract_multimodel_pdb(quiver_file, output, select=select)
+        elif fmt == "cif":
+            extract_mmcif(quiver_file, output, select=select)
+        elif fmt == "parquet":
+            to_arrow(quiver_file, select=select, parquet=output)
+        else:
+            extract_tar(quiver_file, output, select=select)
+    except Exception as e:
+        click.secho(f"❌ Error: {str(e)}", fg="red", err=True)
+        sys.exit(1)
 
 if __name__ == "__main__":
     main()
@@ -12,12 +12,18 @@ from quiver_pdb import qvfrompdbs
 
 @click.command()
 @click.argument("pdb_files", nargs=-1, required=True)
-def main(pdb_files):
+@click.option(
+    "--checksum",
+    type=click.Choice(["crc32", "xxh64"]),
+    default=None,
+    help="Record a per-record checksum for qvverify",
+)
+def main(pdb_files, checksum):
     """
     Converts one or more PDB files into a Quiver-formatted stream.
     Output is printed to stdout.
     """
-    sys.stdout.write(qvfrompdbs(list(pdb_files)))
+    sys.stdout.write(qvfrompdbs(list(pdb_files), checksum=checksum))
 
 if __name__ == "__main__":
     main()
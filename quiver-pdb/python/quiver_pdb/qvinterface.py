@@ -0,0 +1,63 @@
+#!/usr/bin/env python3
+"""
+This script computes inter-chain interface metrics (residue and atom contacts,
+heavy-atom clashes and interface residues per chain) for every record of a
+Quiver (.qv) file.
+
+Usage:
+    qvinterface.py binders.qv
+    qvinterface.py binders.qv --chains A:B --contact-cutoff 4.5
+    qvinterface.py binders.qv --append-scores
+"""
+
+import sys
+import click
+from quiver_pdb import qvinterface
+
+@click.command()
+@click.argument("qvfile", type=click.Path(exists=True, dir_okay=False))
+@click.option(
+    "--chains",
+    default=None,
+    help="Chains on each side of the interface, e.g. A:B or AB:C (default: all chain pairs)",
+)
+@click.option("--contact-cutoff", type=float, default=5.0, help="Heavy-atom contact distance in Å")
+@click.option("--clash-cutoff", type=float, default=2.5, help="Heavy-atom clash distance in Å")
+@click.option(
+    "--output",
+    type=click.Path(dir_okay=False, writable=True),
+    default=None,
+    help="Output path (default: next to QVFILE as .interface.sc)",
+)
+@click.option(
+    "--format",
+    "fmt",
+    type=click.Choice(["tsv", "csv", "jsonl", "parquet"]),
+    default=None,
+    help="Output format (default: from the --output extension, else tsv)",
+)
+@click.option(
+    "--append-scores",
+    is_flag=True,
+    help="Add the metrics as QV_SCORE terms in QVFILE instead of writing a table",
+)
+def main(qvfile, chains, contact_cutoff, clash_cutoff, output, fmt, append_scores):
+    """
+    Computes interface metrics for every record in the Quiver file.
+    """
+    try:
+        qvinterface(
+            qvfile,
+            contact_cutoff=contact_cutoff,
+            clash_cutoff=clash_cutoff,
+            chains=chains,
+            output=output,
+            format=fmt,
+            append_scores=append_scores,
+        )
+    except Exception as e:
+        click.secho(f"❌ Error: {str(e)}", fg="red", err=True)
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
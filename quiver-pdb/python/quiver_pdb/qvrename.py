@@ -4,6 +4,9 @@ Rename the tags in a Quiver file using new tags from stdin or command-line argum
 
 Usage examples:
     qvrename.py my.qv tag1_new tag2_new ... > renamed.qv
+    qvrename.py my.qv --map renames.txt        # two columns: old new
+    qvrename.py my.qv --regex '_0001$' --replace ''
+    qvrename.py my.qv --prefix run2_
 """
 
 import sys
@@ -15,22 +18,44 @@ from quiver_pdb import rename_tags as rust_rename_tags
 @click.command()
 @click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
 @click.argument("new_tags", nargs=-1)
-def rename_tags(quiver_file, new_tags):
+@click.option(
+    "--map",
+    "mapping_file",
+    type=click.Path(exists=True, dir_okay=False),
+    default=None,
+    help="Two-column file of 'old new' tags; only listed tags are renamed",
+)
+@click.option("--regex", "pattern", default=None, help="Regex to search for in every tag")
+@click.option("--replace", "replacement", default="", help="Replacement for --regex ($1 for groups)")
+@click.option("--prefix", default=None, help="Prefix to add to every tag")
+@click.option("--suffix", default=None, help="Suffix to add to every tag")
+def rename_tags(quiver_file, new_tags, mapping_file, pattern, replacement, prefix, suffix):
     """
-    Rename tags in a Quiver file. New tags are read from arguments or stdin.
+    Rename tags in a Quiver file. New tags are read from arguments or stdin,
+    or derived with --map, --regex or --prefix/--suffix.
     """
-    tag_buffers = list(new_tags)
+    try:
+        if mapping_file is not None:
+            rust_rename_tags(quiver_file, mapping_file=mapping_file)
+            return
+        if pattern is not None:
+            rust_rename_tags(quiver_file, pattern=pattern, replacement=replacement)
+            return
+        if prefix is not None or suffix is not None:
+            rust_rename_tags(quiver_file, prefix=prefix, suffix=suffix)
+            return
 
-    # Read from stdin if piped
-    if not sys.stdin.isatty() and stat.S_ISFIFO(os.fstat(0).st_mode):
-        stdin_lines = sys.stdin.read().splitlines()
-        for line in stdin_lines:
-            tag_buffers.extend(line.strip().split())
+        tag_buffers = list(new_tags)
 
-    # Filter out empty entries
-    tags = [tag.strip() for tag in tag_buffers if tag.strip()]
+        # Read from stdin if piped
+        if not sys.stdin.isatty() and stat.S_ISFIFO(os.fstat(0).st_mode):
+            stdin_lines = sys.stdin.read().splitlines()
+            for line in stdin_lines:
+                tag_buffers.extend(line.strip().split())
+
+        # Filter out empty entries
+        tags = [tag.strip() for tag in tag_buffers if tag.strip()]
 
-    try:
         rust_rename_tags(quiver_file, tags)
     except Exception as e:
         click.secho(f"Error renaming tags: {e}", fg="red", err=True)
@@ -38,4 +63,4 @@ def rename_tags(quiver_file, new_tags):
 
 
 if __name__ == "__main__":
-    rename_tags()
\ No newline at end of file
+    rename_tags()
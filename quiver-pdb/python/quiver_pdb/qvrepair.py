@@ -0,0 +1,35 @@
+#!/usr/bin/env python3
+"""
+Recover the intact records of a truncated or corrupted Quiver (.qv) file.
+
+Usage:
+    qvrepair.py damaged.qv repaired.qv
+    qvrepair.py damaged.qv repaired.qv --rename-duplicates
+"""
+
+import sys
+import click
+from quiver_pdb import qvrepair as rust_qvrepair  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.option(
+    "--rename-duplicates",
+    is_flag=True,
+    help="Keep later records with an already-seen tag as <tag>_dupN instead of dropping them",
+)
+def qvrepair(quiver_file, output, rename_duplicates):
+    """
+    Write every intact record of QUIVER_FILE to OUTPUT and report what was dropped.
+    """
+    try:
+        rust_qvrepair(quiver_file, output, rename_duplicates=rename_duplicates)
+    except Exception as e:
+        click.secho(f"Error repairing Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvrepair()
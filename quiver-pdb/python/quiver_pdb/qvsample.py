@@ -0,0 +1,55 @@
+#!/usr/bin/env python3
+"""
+Randomly subsample a Quiver (.qv) file into a new Quiver file.
+
+Usage:
+    qvsample.py big.qv sample.qv -n 1000 --seed 42
+    qvsample.py big.qv sample.qv --fraction 0.1
+    qvsample.py big.qv sample.qv -n 10 --group-regex '^(\\w+?)_\\d+$'
+    qvsample.py big.qv sample.qv -n 50 --score-term ddg --bin-width 5
+"""
+
+import sys
+import click
+from quiver_pdb import qvsample as rust_qvsample  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.option("-n", "n", type=int, default=None, help="Number of records to draw (per stratum)")
+@click.option("--fraction", type=float, default=None, help="Fraction of records to draw (per stratum)")
+@click.option("--seed", type=int, default=0, help="Random seed (default: 0)")
+@click.option(
+    "--group-regex",
+    default=None,
+    help="Stratify by the first capture group of this regex over each tag",
+)
+@click.option("--score-term", default=None, help="Stratify by bins of this QV_SCORE term")
+@click.option("--bin-width", type=float, default=1.0, help="Score bin width (default: 1.0)")
+def qvsample(quiver_file, output, n, fraction, seed, group_regex, score_term, bin_width):
+    """
+    Draw a random subset of QUIVER_FILE and write it to OUTPUT.
+    """
+    if (n is None) == (fraction is None):
+        click.secho("❌ Provide exactly one of -n or --fraction.", fg="red", err=True)
+        sys.exit(1)
+
+    try:
+        rust_qvsample(
+            quiver_file,
+            output,
+            n=n,
+            fraction=fraction,
+            seed=seed,
+            group_regex=group_regex,
+            score_term=score_term,
+            bin_width=bin_width,
+        )
+    except Exception as e:
+        click.secho(f"Error sampling Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvsample()
@@ -4,6 +4,8 @@ This script extracts the scorefile from a Quiver (.qv) file and writes it as a .
 
 Usage:
     qvscorefile.py mydesigns.qv
+    qvscorefile.py mydesigns.qv --output scores.csv
+    qvscorefile.py mydesigns.qv --output scores.parquet --format parquet
 """
 
 import os
@@ -13,12 +15,25 @@ from quiver_pdb import extract_scorefile
 
 @click.command()
 @click.argument("qvfile", type=click.Path(exists=True, dir_okay=False))
-def main(qvfile):
+@click.option(
+    "--output",
+    type=click.Path(dir_okay=False, writable=True),
+    default=None,
+    help="Output path (default: next to QVFILE with the format's extension)",
+)
+@click.option(
+    "--format",
+    "fmt",
+    type=click.Choice(["tsv", "csv", "jsonl", "parquet"]),
+    default=None,
+    help="Output format (default: from the --output extension, else tsv)",
+)
+def main(qvfile, output, fmt):
     """
     Extracts the scorefile from the provided Quiver file and saves it as a .sc file.
     """
     try:
-        extract_scorefile(qvfile)
+        extract_scorefile(qvfile, output=output, format=fmt)
     except Exception as e:
         click.secho(f"❌ Error: {str(e)}", fg="red", err=True)
         sys.exit(1)
@@ -0,0 +1,34 @@
+#!/usr/bin/env python3
+"""
+Keep only the selected atoms of every record in a Quiver (.qv) file.
+
+Usage:
+    qvselect.py designs.qv binder.qv "chain A"
+    qvselect.py designs.qv core.qv "chain A and resi 10-50 and not hydrogen"
+
+Selections combine chain, resi, resn, name and element with and/or/not;
+see the qvselect docstring for the full syntax.
+"""
+
+import sys
+import click
+from quiver_pdb import qvselect as rust_qvselect  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.argument("selection")
+def qvselect(quiver_file, output, selection):
+    """
+    Write QUIVER_FILE to OUTPUT keeping only the atoms matching SELECTION.
+    """
+    try:
+        rust_qvselect(quiver_file, selection, output)
+    except Exception as e:
+        click.secho(f"Error selecting atoms: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvselect()
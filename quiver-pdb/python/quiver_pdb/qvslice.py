@@ -5,6 +5,9 @@ Slice a specific set of tags from a Quiver file into another Quiver file.
 Usage:
     qvslice.py big.qv tag1 tag2 ... > sliced.qv
     echo "tag1 tag2" | qvslice.py big.qv > sliced.qv
+    qvslice.py big.qv 'design_*' --match glob --output sliced.qv
+    qvslice.py big.qv '_rejected$' --match regex --invert --output kept.qv
+    qvslice.py big.qv tag3 tag1 tag2 --ordered --output sliced.qv
 """
 import sys
 import click
@@ -14,7 +17,26 @@ from quiver_pdb import qvslice as rust_qvslice  # Rust로 구현된 quiver_pdb 
 @click.command()
 @click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
 @click.argument("tags", nargs=-1)
-def qvslice(quiver_file, tags):
+@click.option(
+    "--match",
+    "match_mode",
+    type=click.Choice(["exact", "glob", "regex"]),
+    default="exact",
+    help="How TAGS are interpreted (default: exact tag names)",
+)
+@click.option("--invert", is_flag=True, help="Keep every tag that does NOT match TAGS")
+@click.option(
+    "--ordered",
+    is_flag=True,
+    help="Emit records in the order TAGS were given instead of file order",
+)
+@click.option(
+    "--output",
+    type=click.Path(dir_okay=False, writable=True),
+    default=None,
+    help="Write the sliced Quiver file here instead of stdout",
+)
+def qvslice(quiver_file, tags, match_mode, invert, ordered, output):
     """
     Extract selected TAGS from QUIVER_FILE and output to stdout.
     If no TAGS are provided as arguments, they are read from stdin.
@@ -37,7 +59,14 @@ def qvslice(quiver_file, tags):
         sys.exit(1)
 
     try:
-        rust_qvslice(quiver_file, tag_list)
+        rust_qvslice(
+            quiver_file,
+            tag_list,
+            match_mode=match_mode,
+            invert=invert,
+            ordered=ordered,
+            output=output,
+        )
     except Exception as e:
         click.secho(f"Error slicing Quiver file: {e}", fg="red", err=True)
         sys.exit(1)
@@ -45,4 +74,3 @@ def qvslice(quiver_file, tags):
 
 if __name__ == "__main__":
     qvslice()
-
@@ -0,0 +1,34 @@
+#!/usr/bin/env python3
+"""
+Sort the records of a Quiver (.qv) file by tag or by a score term.
+
+Usage:
+    qvsort.py mydesigns.qv sorted.qv
+    qvsort.py mydesigns.qv sorted.qv --natural
+    qvsort.py mydesigns.qv sorted.qv --score-term ddg --reverse
+"""
+
+import sys
+import click
+from quiver_pdb import qvsort as rust_qvsort  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.option("--score-term", default=None, help="Sort by this QV_SCORE term instead of the tag")
+@click.option("--natural", is_flag=True, help="Natural tag order (design_2 before design_10)")
+@click.option("--reverse", is_flag=True, help="Sort in descending order")
+def qvsort(quiver_file, output, score_term, natural, reverse):
+    """
+    Sort the records of QUIVER_FILE and write them to OUTPUT.
+    """
+    try:
+        rust_qvsort(quiver_file, output, score_term=score_term, natural=natural, reverse=reverse)
+    except Exception as e:
+        click.secho(f"Error sorting Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvsort()
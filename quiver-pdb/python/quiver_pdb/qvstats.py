@@ -0,0 +1,48 @@
+#!/usr/bin/env python3
+"""
+This script computes per-record structural statistics (residue, chain and atom
+counts, chain lengths, radius of gyration, mean B-factor/pLDDT, missing
+backbone atoms and chain breaks) for a Quiver (.qv) file.
+
+Usage:
+    qvstats.py mydesigns.qv
+    qvstats.py mydesigns.qv --output stats.csv
+    qvstats.py mydesigns.qv --append-scores
+"""
+
+import sys
+import click
+from quiver_pdb import qvstats
+
+@click.command()
+@click.argument("qvfile", type=click.Path(exists=True, dir_okay=False))
+@click.option(
+    "--output",
+    type=click.Path(dir_okay=False, writable=True),
+    default=None,
+    help="Output path (default: next to QVFILE as .stats.sc)",
+)
+@click.option(
+    "--format",
+    "fmt",
+    type=click.Choice(["tsv", "csv", "jsonl", "parquet"]),
+    default=None,
+    help="Output format (default: from the --output extension, else tsv)",
+)
+@click.option(
+    "--append-scores",
+    is_flag=True,
+    help="Add the statistics as QV_SCORE terms in QVFILE instead of writing a table",
+)
+def main(qvfile, output, fmt, append_scores):
+    """
+    Computes structural statistics for every record in the Quiver file.
+    """
+    try:
+        qvstats(qvfile, output=output, format=fmt, append_scores=append_scores)
+    except Exception as e:
+        click.secho(f"❌ Error: {str(e)}", fg="red", err=True)
+        sys.exit(1)
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+"""
+Apply structural transforms to every record of a Quiver (.qv) file.
+
+Usage:
+    qvtransform.py designs.qv clean.qv --strip-hydrogens --strip-waters --renumber
+    qvtransform.py designs.qv swapped.qv --rechain A:B --rechain B:A
+    qvtransform.py designs.qv centered.qv --recenter --reserial
+
+Steps run in a fixed order: strip, rechain, renumber, recenter, reserial.
+"""
+
+import sys
+import click
+from quiver_pdb import qvtransform as rust_qvtransform  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+def _parse_rechain(ctx, param, values):
+    mapping = {}
+    for value in values:
+        old, sep, new = value.partition(":")
+        if not sep:
+            raise click.BadParameter(f"expected OLD:NEW, got '{value}'")
+        mapping[old] = new
+    return mapping
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+@click.argument("output", type=click.Path(dir_okay=False, writable=True))
+@click.option("--renumber", is_flag=True, help="Number residues from 1 in each chain")
+@click.option(
+    "--rechain",
+    multiple=True,
+    callback=_parse_rechain,
+    help="Rename a chain as OLD:NEW (repeatable)",
+)
+@click.option("--strip-hydrogens", is_flag=True, help="Remove hydrogen atoms")
+@click.option("--strip-hetatm", is_flag=True, help="Remove HETATM records (ligands and waters)")
+@click.option("--strip-waters", is_flag=True, help="Remove water molecules")
+@click.option("--recenter", is_flag=True, help="Move the centroid of each record to the origin")
+@click.option("--reserial", is_flag=True, help="Number atoms from 1")
+def qvtransform(quiver_file, output, renumber, rechain, strip_hydrogens, strip_hetatm, strip_waters, recenter, reserial):
+    """
+    Write QUIVER_FILE to OUTPUT with the selected transforms applied.
+    """
+    try:
+        rust_qvtransform(
+            quiver_file,
+            output,
+            renumber=renumber,
+            rechain=rechain or None,
+            strip_hydrogens=strip_hydrogens,
+            strip_hetatm=strip_hetatm,
+            strip_waters=strip_waters,
+            recenter=recenter,
+            reserial=reserial,
+        )
+    except Exception as e:
+        click.secho(f"Error transforming Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvtransform()
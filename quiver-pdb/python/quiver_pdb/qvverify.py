@@ -0,0 +1,31 @@
+#!/usr/bin/env python3
+"""
+Verify the per-record checksums of a Quiver (.qv) file.
+
+Usage:
+    qvverify.py mydesigns.qv
+"""
+
+import sys
+import click
+from quiver_pdb import qvverify as rust_qvverify  # Rust로 구현된 quiver_pdb 모듈 import
+
+
+@click.command()
+@click.argument("quiver_file", type=click.Path(exists=True, dir_okay=False))
+def qvverify(quiver_file):
+    """
+    Check every record of QUIVER_FILE against its QV_CHECKSUM line.
+    Exits with status 1 if any record is truncated or corrupted.
+    """
+    try:
+        problems = rust_qvverify(quiver_file)
+    except Exception as e:
+        click.secho(f"Error verifying Quiver file: {e}", fg="red", err=True)
+        sys.exit(1)
+    if problems:
+        sys.exit(1)
+
+
+if __name__ == "__main__":
+    qvverify()
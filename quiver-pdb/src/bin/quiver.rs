@@ -0,0 +1,378 @@
+//! `quiver`: the Quiver command-line tools as a single native binary.
+//!
+//! Subcommands mirror the `qv*` Python scripts and share `QuiverCore`, so
+//! no Python interpreter is needed. Status messages go to stderr; Quiver
+//! output goes to stdout unless `--output` is given.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use quiver_pdb::{
+    write_pdb_files, ChecksumAlgo, QuiverCore, QuiverError, Result, ScoreCondition, ScoreFormat, Selection, TagMatcher,
+    TagRename, VerifyStatus, DEFAULT_BATCH_SIZE,
+};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "quiver", version, about = "Work with Quiver (.qv) archives of PDB structures")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExtractFormat {
+    /// One PDB file per tag
+    Pdb,
+    /// A single multi-MODEL PDB file
+    Multimodel,
+    /// One mmCIF file per tag
+    Cif,
+    /// A .tar or .tar.gz of PDB files
+    Tar,
+    /// A Parquet table with one row per tag and per-atom list columns
+    Parquet,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MatchMode {
+    Exact,
+    Glob,
+    Regex,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List all tags
+    Ls { quiver_file: String },
+    /// Extract structures as PDB, multi-model PDB, mmCIF or a tarball
+    Extract {
+        quiver_file: String,
+        #[arg(long, value_enum, default_value = "pdb")]
+        format: ExtractFormat,
+        /// Output directory (pdb, cif) or file (multimodel, tar, parquet)
+        #[arg(long)]
+        output: Option<String>,
+        /// Only write atoms matching a selection, e.g. "chain A and not hydrogen"
+        #[arg(long)]
+        select: Option<String>,
+    },
+    /// Copy selected tags into a new Quiver file; tags are read from stdin if none are given
+    Slice {
+        quiver_file: String,
+        tags: Vec<String>,
+        #[arg(long = "match", value_enum, default_value = "exact")]
+        match_mode: MatchMode,
+        /// Keep every tag that does NOT match
+        #[arg(long)]
+        invert: bool,
+        /// Emit records in the order the tags were given
+        #[arg(long)]
+        ordered: bool,
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Split into files of NTAGS records each
+    Split {
+        quiver_file: String,
+        ntags: usize,
+        #[arg(long, default_value = "split")]
+        prefix: String,
+        #[arg(long, default_value = ".")]
+        output_dir: String,
+    },
+    /// Rename tags in place; new tags are read from stdin if none are given
+    Rename {
+        quiver_file: String,
+        new_tags: Vec<String>,
+        /// Two-column file of 'old new' tags; only listed tags are renamed
+        #[arg(long = "map")]
+        mapping_file: Option<String>,
+        /// Regex to search for in every tag
+        #[arg(long = "regex")]
+        pattern: Option<String>,
+        /// Replacement for --regex ($1 for groups)
+        #[arg(long = "replace", default_value = "")]
+        replacement: String,
+        #[arg(long)]
+        prefix: Option<String>,
+        #[arg(long)]
+        suffix: Option<String>,
+    },
+    /// Write the score table (default: next to the Quiver file)
+    Scorefile {
+        quiver_file: String,
+        #[arg(long)]
+        output: Option<String>,
+        /// tsv, csv, jsonl or parquet (default: from --output, else tsv)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Pack PDB files into a Quiver file, tagged by file name
+    Frompdbs {
+        #[arg(required = true)]
+        pdb_files: Vec<String>,
+        /// crc32 or xxh64
+        #[arg(long)]
+        checksum: Option<String>,
+        /// Start the file with a QV_HEADER block
+        #[arg(long)]
+        header: bool,
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Concatenate Quiver files; tags must be unique across inputs
+    Cat {
+        #[arg(required = true)]
+        quiver_files: Vec<String>,
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Keep records whose scores meet every condition, e.g. 'ddg<-10'
+    Filter {
+        quiver_file: String,
+        #[arg(required = true)]
+        conditions: Vec<String>,
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Verify record checksums; exits with 1 if any record is damaged
+    Check { quiver_file: String },
+}
+
+/// Whether both paths name the same existing file.
+fn same_file(a: &str, b: &str) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// A buffered file, or stdout when no path is given. The file must not be
+/// one of `inputs`, which are still being read (and may be memory-mapped).
+fn open_output(path: Option<&str>, inputs: &[String]) -> Result<Box<dyn Write>> {
+    if path.is_some_and(|path| inputs.iter().any(|input| same_file(input, path))) {
+        return Err(QuiverError::Invalid("Output file must not be one of the inputs.".to_string()));
+    }
+    Ok(match path {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+/// Whitespace-separated words from stdin, unless it is a terminal.
+fn stdin_words() -> Result<Vec<String>> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Ok(Vec::new());
+    }
+    let mut text = String::new();
+    stdin.read_to_string(&mut text)?;
+    Ok(text.split_whitespace().map(String::from).collect())
+}
+
+fn open(quiver_file: &str) -> Result<QuiverCore> {
+    QuiverCore::new(quiver_file.to_string(), "r".to_string())
+}
+
+fn extract(quiver_file: &str, format: ExtractFormat, output: Option<String>, select: Option<String>) -> Result<()> {
+    let selection = select.as_deref().map(Selection::parse).transpose()?;
+    let core = open(quiver_file)?;
+    let required = || match &output {
+        Some(path) if same_file(path, quiver_file) => {
+            Err(QuiverError::Invalid("Output file must not be one of the inputs.".to_string()))
+        }
+        Some(path) => Ok(path.clone()),
+        None => Err(QuiverError::Invalid("--output is required for this format".to_string())),
+    };
+    let count = match format {
+        ExtractFormat::Pdb => {
+            let outdir = output.clone().unwrap_or_else(|| ".".to_string());
+            std::fs::create_dir_all(&outdir)?;
+            let mut count = 0;
+            for record in core.records()? {
+                let record = record?;
+                let outfn = Path::new(&outdir).join(format!("{}.pdb", record.tag));
+                if outfn.exists() {
+                    eprintln!("⚠️  File {} already exists, skipping", outfn.display());
+                    continue;
+                }
+                let lines = match &selection {
+                    Some(selection) => selection.filter_lines(&record.lines),
+                    None => record.lines,
+                };
+                if lines.is_empty() {
+                    eprintln!("⚠️  No atoms selected in {}, skipping", record.tag);
+                    continue;
+                }
+                let mut out = BufWriter::new(File::create(&outfn)?);
+                for line in &lines {
+                    out.write_all(line.as_bytes())?;
+                }
+                out.flush()?;
+                count += 1;
+            }
+            count
+        }
+        ExtractFormat::Multimodel => core.write_multimodel_pdb(&required()?, selection.as_ref())?,
+        ExtractFormat::Cif => core.write_mmcif(&required()?, selection.as_ref())?,
+        ExtractFormat::Tar => core.write_tar(&required()?, selection.as_ref())?,
+        ExtractFormat::Parquet => core
+            .structure_batches(None, selection.as_ref(), DEFAULT_BATCH_SIZE)?
+            .write_parquet(&required()?)?,
+    };
+    eprintln!("✅ Extracted {} structures from {}", count, quiver_file);
+    Ok(())
+}
+
+fn rename(
+    quiver_file: &str,
+    mut new_tags: Vec<String>,
+    mapping_file: Option<String>,
+    pattern: Option<String>,
+    replacement: String,
+    prefix: Option<String>,
+    suffix: Option<String>,
+) -> Result<()> {
+    let rename = if let Some(path) = mapping_file {
+        TagRename::from_mapping_file(&path)?
+    } else if let Some(pattern) = pattern {
+        let pattern = regex::Regex::new(&pattern).map_err(|e| QuiverError::Invalid(e.to_string()))?;
+        TagRename::Regex { pattern, replacement }
+    } else if prefix.is_some() || suffix.is_some() {
+        TagRename::Affix { prefix: prefix.unwrap_or_default(), suffix: suffix.unwrap_or_default() }
+    } else {
+        if new_tags.is_empty() {
+            new_tags = stdin_words()?;
+        }
+        TagRename::List(new_tags)
+    };
+    let mut core = open(quiver_file)?;
+    let changed = core.rename_tags(&rename)?;
+    eprintln!("✅ Renamed {} tags in {}", changed, quiver_file);
+    Ok(())
+}
+
+fn check(quiver_file: &str) -> Result<ExitCode> {
+    let results = open(quiver_file)?.verify()?;
+    let mut problems = 0;
+    let mut unchecked = 0;
+    for result in &results {
+        match result.status {
+            VerifyStatus::Ok => {}
+            VerifyStatus::Unchecked => unchecked += 1,
+            _ => {
+                problems += 1;
+                println!("{}\t{}\t{}", result.tag, result.offset, result.status.name());
+            }
+        }
+    }
+    if unchecked > 0 {
+        eprintln!("⚠️  {} records have no checksum", unchecked);
+    }
+    if problems > 0 {
+        eprintln!("❌ {} of {} records failed verification", problems, results.len());
+        return Ok(ExitCode::FAILURE);
+    }
+    eprintln!("✅ Verified {} records in {}", results.len() - unchecked, quiver_file);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run(command: Command) -> Result<ExitCode> {
+    match command {
+        Command::Ls { quiver_file } => {
+            let mut out = open_output(None, &[])?;
+            for tag in open(&quiver_file)?.get_tags() {
+                writeln!(out, "{}", tag)?;
+            }
+            out.flush()?;
+        }
+        Command::Extract { quiver_file, format, output, select } => extract(&quiver_file, format, output, select)?,
+        Command::Slice { quiver_file, mut tags, match_mode, invert, ordered, output } => {
+            if tags.is_empty() {
+                tags = stdin_words()?;
+            }
+            if tags.is_empty() {
+                return Err(QuiverError::Invalid("No tags provided. Provide tags as arguments or via stdin.".to_string()));
+            }
+            let mode = match match_mode {
+                MatchMode::Exact => "exact",
+                MatchMode::Glob => "glob",
+                MatchMode::Regex => "regex",
+            };
+            let matcher = TagMatcher::new(&tags, mode, invert)?;
+            let core = open(&quiver_file)?;
+            let mut out = open_output(output.as_deref(), std::slice::from_ref(&quiver_file))?;
+            let written = core.write_slice(&matcher, ordered, &mut out)?;
+            out.flush()?;
+            if matches!(match_mode, MatchMode::Exact) && !invert {
+                let written: HashSet<&String> = written.iter().collect();
+                for tag in tags.iter().filter(|tag| !written.contains(tag)) {
+                    eprintln!("⚠️  Tag not found in Quiver file: {}", tag);
+                }
+            }
+        }
+        Command::Split { quiver_file, ntags, prefix, output_dir } => {
+            if ntags == 0 {
+                return Err(QuiverError::Invalid("NTAGS must be a positive integer.".to_string()));
+            }
+            open(&quiver_file)?.split(ntags, &output_dir, &prefix)?;
+            eprintln!("✅ Files written to {} with prefix '{}'", output_dir, prefix);
+        }
+        Command::Rename { quiver_file, new_tags, mapping_file, pattern, replacement, prefix, suffix } => {
+            rename(&quiver_file, new_tags, mapping_file, pattern, replacement, prefix, suffix)?
+        }
+        Command::Scorefile { quiver_file, output, format } => {
+            let table = open(&quiver_file)?.score_table()?;
+            if table.rows.is_empty() {
+                return Err(QuiverError::Invalid("No score lines found in Quiver file.".to_string()));
+            }
+            let format = match format {
+                Some(name) => ScoreFormat::from_name(&name)?,
+                None => output.as_deref().map(ScoreFormat::from_path).unwrap_or(ScoreFormat::Tsv),
+            };
+            let outfn = output.unwrap_or_else(|| {
+                Path::new(&quiver_file).with_extension(format.extension()).to_string_lossy().into_owned()
+            });
+            if same_file(&outfn, &quiver_file) {
+                return Err(QuiverError::Invalid("Output file must not be one of the inputs.".to_string()));
+            }
+            table.write(&outfn, format)?;
+            eprintln!("✅ Wrote {} rows to {}", table.rows.len(), outfn);
+        }
+        Command::Frompdbs { pdb_files, checksum, header, output } => {
+            let checksum = checksum.as_deref().map(ChecksumAlgo::from_name).transpose()?;
+            let mut out = open_output(output.as_deref(), &pdb_files)?;
+            write_pdb_files(&pdb_files, checksum, header, &mut out)?;
+            out.flush()?;
+        }
+        Command::Cat { quiver_files, output } => {
+            let mut out = open_output(output.as_deref(), &quiver_files)?;
+            let count = QuiverCore::merge(&quiver_files, &mut out)?;
+            out.flush()?;
+            eprintln!("✅ Merged {} tags from {} files", count, quiver_files.len());
+        }
+        Command::Filter { quiver_file, conditions, output } => {
+            let conditions = conditions.iter().map(|spec| ScoreCondition::parse(spec)).collect::<Result<Vec<_>>>()?;
+            let core = open(&quiver_file)?;
+            let mut out = open_output(output.as_deref(), std::slice::from_ref(&quiver_file))?;
+            let kept = core.filter_records(&conditions, &mut out)?;
+            out.flush()?;
+            eprintln!("✅ Kept {} of {} records", kept, core.size());
+        }
+        Command::Check { quiver_file } => return check(&quiver_file),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Per-record integrity checksums (`QV_CHECKSUM` lines) and verification.
+//!
+//! The checksum covers the record payload: every line of the record that is
+//! not a `QV_*` annotation, each including its trailing newline.
+//!
+//! ```text
+//! QV_CHECKSUM design_1 crc32=1a2b3c4d|bytes=81
+//! ```
+
+use crate::score::{encode_score, parse_score_str};
+use crate::{is_annotation, QuiverCore, QuiverError, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    Crc32,
+    Xxh64,
+}
+
+impl ChecksumAlgo {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "crc32" => Ok(ChecksumAlgo::Crc32),
+            "xxh64" | "xxhash" => Ok(ChecksumAlgo::Xxh64),
+            _ => Err(QuiverError::Invalid(format!("Checksum must be 'crc32' or 'xxh64', not '{}'", name))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgo::Crc32 => "crc32",
+            ChecksumAlgo::Xxh64 => "xxh64",
+        }
+    }
+
+    pub fn digest(self, payload: &[u8]) -> String {
+        match self {
+            ChecksumAlgo::Crc32 => format!("{:08x}", crc32fast::hash(payload)),
+            ChecksumAlgo::Xxh64 => format!("{:016x}", xxhash_rust::xxh64::xxh64(payload, 0)),
+        }
+    }
+
+    /// The `QV_CHECKSUM` line (without newline) for a record payload.
+    pub fn annotation(self, tag: &str, payload: &[u8]) -> String {
+        let pairs = [
+            (self.name().to_string(), self.digest(payload)),
+            ("bytes".to_string(), payload.len().to_string()),
+        ];
+        format!("QV_CHECKSUM {} {}", tag, encode_score(&pairs))
+    }
+}
+
+/// Outcome of verifying one record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    Ok,
+    /// The record has no `QV_CHECKSUM` line.
+    Unchecked,
+    /// The payload is shorter than recorded, or the file ends mid-line.
+    Truncated,
+    /// The payload digest or length differs from the recorded one.
+    Mismatch,
+    /// The `QV_CHECKSUM` line could not be understood.
+    Malformed,
+}
+
+impl VerifyStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VerifyStatus::Ok => "ok",
+            VerifyStatus::Unchecked => "unchecked",
+            VerifyStatus::Truncated => "truncated",
+            VerifyStatus::Mismatch => "mismatch",
+            VerifyStatus::Malformed => "malformed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyResult {
+    pub tag: String,
+    /// Byte offset of the record's `QV_TAG` line.
+    pub offset: u64,
+    pub status: VerifyStatus,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+pub(crate) fn verify_record(tag: &str, offset: u64, bytes: &[u8]) -> VerifyResult {
+    let mut payload = Vec::with_capacity(bytes.len());
+    let mut checksum_line = None;
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        if line.starts_with(b"QV_CHECKSUM") {
+            checksum_line = Some(String::from_utf8_lossy(line).into_owned());
+        } else if !is_annotation(line) && !line.starts_with(b"QV_TAG") {
+            payload.extend_from_slice(line);
+        }
+    }
+    let mut result = VerifyResult { tag: tag.to_string(), offset, status: VerifyStatus::Ok, expected: None, actual: None };
+    let ends_mid_line = !bytes.ends_with(b"\n");
+
+    let Some(line) = checksum_line else {
+        result.status = if ends_mid_line { VerifyStatus::Truncated } else { VerifyStatus::Unchecked };
+        return result;
+    };
+    let fields = parse_score_str(line.split_whitespace().nth(2).unwrap_or(""));
+    let expected_len = fields.iter().find(|(k, _)| k == "bytes").and_then(|(_, v)| v.parse::<usize>().ok());
+    let digest = fields
+        .iter()
+        .find_map(|(k, v)| ChecksumAlgo::from_name(k).ok().map(|algo| (algo, v.clone())));
+    let (Some(expected_len), Some((algo, expected))) = (expected_len, digest) else {
+        result.status = VerifyStatus::Malformed;
+        return result;
+    };
+
+    let actual = algo.digest(&payload);
+    result.status = if payload.len() < expected_len || ends_mid_line {
+        VerifyStatus::Truncated
+    } else if actual != expected || payload.len() != expected_len {
+        VerifyStatus::Mismatch
+    } else {
+        VerifyStatus::Ok
+    };
+    result.expected = Some(format!("{}:{}", algo.name(), expected));
+    result.actual = Some(format!("{}:{}", algo.name(), actual));
+    result
+}
+
+impl QuiverCore {
+    /// Check every record against its `QV_CHECKSUM` line.
+    pub fn verify(&self) -> Result<Vec<VerifyResult>> {
+        let mut file = File::open(&self.fnm)?;
+        let mut buf = Vec::new();
+        let mut results = Vec::new();
+        for offset in self.record_offsets()? {
+            buf.resize((offset.end - offset.start) as usize, 0);
+            file.seek(SeekFrom::Start(offset.start))?;
+            file.read_exact(&mut buf)?;
+            results.push(verify_record(&offset.tag, offset.start, &buf));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATOM: &str = "ATOM      1  CA  ALA A   1       1.000   2.000   3.000  1.00 10.00           C\n";
+
+    fn record(algo: ChecksumAlgo, payload: &str) -> String {
+        format!("QV_TAG t\nQV_SCORE t ddg=1\n{}\n{}", algo.annotation("t", payload.as_bytes()), payload)
+    }
+
+    #[test]
+    fn intact_records_verify_ok() {
+        for algo in [ChecksumAlgo::Crc32, ChecksumAlgo::Xxh64] {
+            let result = verify_record("t", 7, record(algo, ATOM).as_bytes());
+            assert_eq!(result.status, VerifyStatus::Ok);
+            assert_eq!(result.offset, 7);
+            assert_eq!(result.expected, result.actual);
+        }
+    }
+
+    #[test]
+    fn verify_record_status_cases() {
+        let status = |bytes: &str| verify_record("t", 0, bytes.as_bytes()).status;
+        let good = record(ChecksumAlgo::Crc32, ATOM);
+
+        assert_eq!(status(&format!("QV_TAG t\n{}", ATOM)), VerifyStatus::Unchecked);
+        assert_eq!(status(&format!("QV_TAG t\n{}", ATOM.trim_end())), VerifyStatus::Truncated);
+        assert_eq!(status(&good[..good.len() - 10]), VerifyStatus::Truncated);
+        assert_eq!(status(&good[..good.len() - ATOM.len()]), VerifyStatus::Truncated);
+        assert_eq!(status(&good.replace("2.000", "2.001")), VerifyStatus::Mismatch);
+        assert_eq!(status(&format!("{}{}", good, ATOM)), VerifyStatus::Mismatch);
+        assert_eq!(status(&good.replace("crc32=", "md5=")), VerifyStatus::Malformed);
+        assert_eq!(status(&good.replace("|bytes=", "|size=")), VerifyStatus::Malformed);
+    }
+
+    #[test]
+    fn algorithm_names() {
+        assert_eq!(ChecksumAlgo::from_name("xxhash").unwrap(), ChecksumAlgo::Xxh64);
+        assert_eq!(ChecksumAlgo::from_name(ChecksumAlgo::Crc32.name()).unwrap(), ChecksumAlgo::Crc32);
+        assert!(matches!(ChecksumAlgo::from_name("md5"), Err(QuiverError::Invalid(_))));
+    }
+}
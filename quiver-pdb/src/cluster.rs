@@ -0,0 +1,171 @@
+//! Structural clustering of records by CA-RMSD or TM-score.
+
+use crate::score::score_value;
+use crate::select::Selection;
+use crate::superpose::{superpose, tm_score, Coords};
+use crate::{QuiverCore, QuiverError, Result};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// When two records count as the same structure.
+#[derive(Debug, Clone, Copy)]
+pub enum ClusterMetric {
+    /// CA-RMSD after superposition at or below the threshold (Å).
+    Rmsd(f64),
+    /// TM-score at or above the threshold, normalized by the longer model.
+    TmScore(f64),
+}
+
+impl ClusterMetric {
+    pub fn from_name(name: &str, threshold: f64) -> Result<Self> {
+        match name {
+            "rmsd" => Ok(ClusterMetric::Rmsd(threshold)),
+            "tm" | "tmscore" | "tm-score" => Ok(ClusterMetric::TmScore(threshold)),
+            _ => Err(QuiverError::Invalid(format!("Cluster metric must be 'rmsd' or 'tmscore', not '{}'", name))),
+        }
+    }
+
+    /// Records that cannot be compared (fewer than 3 paired CA atoms) are
+    /// never similar.
+    fn similar(self, a: &Coords, b: &Coords) -> bool {
+        match self {
+            ClusterMetric::Rmsd(cutoff) => superpose(a, b).is_ok_and(|s| s.rmsd <= cutoff),
+            ClusterMetric::TmScore(cutoff) => {
+                tm_score(a, b, a.xyz.len().max(b.xyz.len())).is_some_and(|tm| tm >= cutoff)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterMethod {
+    /// Each record joins the first representative it is similar to, or
+    /// becomes a new representative. Compares against representatives only.
+    Leader,
+    /// Repeatedly take the record with the most unassigned neighbours as a
+    /// representative, together with those neighbours. Compares all pairs,
+    /// so time grows with the square of the number of records; memory stays
+    /// linear.
+    Greedy,
+}
+
+impl ClusterMethod {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "leader" => Ok(ClusterMethod::Leader),
+            "greedy" => Ok(ClusterMethod::Greedy),
+            _ => Err(QuiverError::Invalid(format!("Cluster method must be 'leader' or 'greedy', not '{}'", name))),
+        }
+    }
+}
+
+/// Cluster assignment of every clustered record.
+#[derive(Debug, Default)]
+pub struct Clustering {
+    /// Records in the order they were considered.
+    pub tags: Vec<String>,
+    /// Cluster id of each record; ids index `representatives`.
+    pub cluster: Vec<usize>,
+    pub representatives: Vec<String>,
+}
+
+fn leader(coords: &[Coords], metric: ClusterMetric) -> (Vec<usize>, Vec<usize>) {
+    let mut leaders: Vec<usize> = Vec::new();
+    let mut cluster = Vec::with_capacity(coords.len());
+    for (i, c) in coords.iter().enumerate() {
+        match leaders.par_iter().position_first(|&l| metric.similar(c, &coords[l])) {
+            Some(id) => cluster.push(id),
+            None => {
+                cluster.push(leaders.len());
+                leaders.push(i);
+            }
+        }
+    }
+    (cluster, leaders)
+}
+
+fn greedy(coords: &[Coords], metric: ClusterMetric) -> (Vec<usize>, Vec<usize>) {
+    // Neighbour lists would need memory quadratic in the number of records,
+    // so only neighbour counts are kept and the neighbours of a record are
+    // recomputed when it joins a cluster.
+    let n = coords.len();
+    let similar = |i: usize, j: usize| metric.similar(&coords[i.min(j)], &coords[i.max(j)]);
+    let unassigned = |cluster: &[usize], i: usize| -> Vec<usize> {
+        (0..n)
+            .into_par_iter()
+            .filter(|&j| j != i && cluster[j] == usize::MAX && similar(i, j))
+            .collect()
+    };
+
+    let mut cluster = vec![usize::MAX; n];
+    let mut count: Vec<usize> = (0..n).into_par_iter().map(|i| unassigned(&cluster, i).len()).collect();
+    let mut centers = Vec::new();
+    let mut remaining = n;
+    while remaining > 0 {
+        // Most unassigned neighbours; ties go to the earliest record.
+        let center = (0..n)
+            .filter(|&i| cluster[i] == usize::MAX)
+            .max_by(|&a, &b| count[a].cmp(&count[b]).then(b.cmp(&a)))
+            .unwrap_or(0);
+        let id = centers.len();
+        centers.push(center);
+        let mut members = unassigned(&cluster, center);
+        members.push(center);
+        for &k in &members {
+            cluster[k] = id;
+            remaining -= 1;
+        }
+        for &k in &members {
+            for m in unassigned(&cluster, k) {
+                count[m] = count[m].saturating_sub(1);
+            }
+        }
+    }
+    (cluster, centers)
+}
+
+impl QuiverCore {
+    /// Cluster `tags` on their CA atoms. With `score_term`, records are
+    /// considered from the lowest score up (highest with `descending`, for
+    /// terms such as pLDDT), so the best-scoring record of a cluster tends
+    /// to become its representative.
+    pub fn cluster(
+        &self,
+        tags: &[String],
+        metric: ClusterMetric,
+        method: ClusterMethod,
+        score_term: Option<&str>,
+        descending: bool,
+    ) -> Result<Clustering> {
+        let mut order: Vec<String> = tags.to_vec();
+        if let Some(term) = score_term {
+            let mut scores: HashMap<String, f64> = HashMap::new();
+            for record in self.records()? {
+                let record = record?;
+                if let Some(value) = record.score.as_deref().and_then(|s| score_value(s, term)) {
+                    scores.insert(record.tag, value);
+                }
+            }
+            // Records without the term go last, in their original order.
+            order.sort_by(|a, b| match (scores.get(a), scores.get(b)) {
+                (Some(x), Some(y)) if descending => y.total_cmp(x),
+                (Some(x), Some(y)) => x.total_cmp(y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            });
+        }
+
+        let ca = Selection::for_atoms("ca")?;
+        let coords = order
+            .iter()
+            .map(|tag| self.coords(tag, &ca))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (cluster, representatives) = match method {
+            ClusterMethod::Leader => leader(&coords, metric),
+            ClusterMethod::Greedy => greedy(&coords, metric),
+        };
+        let representatives = representatives.into_iter().map(|i| order[i].clone()).collect();
+        Ok(Clustering { tags: order, cluster, representatives })
+    }
+}
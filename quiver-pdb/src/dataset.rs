@@ -0,0 +1,329 @@
+//! Bulk export of whole archives as Arrow record batches, one row per
+//! record, for building datasets without extracting PDB files.
+//!
+//! Columns are `tag`, `scores` (a struct with one field per score term,
+//! omitted when no record has scores), `sequence` and per-atom list
+//! columns. Atoms are kept as they appear in the file, alternate locations
+//! included.
+
+use crate::pdb::{one_letter, parse_atoms, Atom};
+use crate::score::parse_score_str;
+use crate::select::Selection;
+use crate::{QuiverCore, QuiverError, QuiverRecord, QuiverRecords, Result};
+use arrow_array::builder::{FixedSizeListBuilder, Float32Builder, Int32Builder, ListBuilder, StringBuilder};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, RecordBatchReader, StringArray, StructArray};
+use arrow_schema::{ArrowError, DataType, Field, Fields, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+/// Records per batch unless the caller asks otherwise.
+pub const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// A score term and whether every value it takes is a number.
+struct ScoreColumn {
+    name: String,
+    numeric: bool,
+}
+
+fn list_of(item: DataType) -> DataType {
+    DataType::List(Arc::new(Field::new_list_field(item, true)))
+}
+
+fn schema(scores: &[ScoreColumn]) -> Schema {
+    let mut fields = vec![Field::new("tag", DataType::Utf8, false)];
+    if !scores.is_empty() {
+        let terms: Fields = scores
+            .iter()
+            .map(|column| {
+                let data_type = if column.numeric { DataType::Float64 } else { DataType::Utf8 };
+                Field::new(&column.name, data_type, true)
+            })
+            .collect();
+        fields.push(Field::new("scores", DataType::Struct(terms), false));
+    }
+    let xyz = DataType::FixedSizeList(Arc::new(Field::new_list_field(DataType::Float32, true)), 3);
+    fields.extend([
+        Field::new("sequence", DataType::Utf8, false),
+        Field::new("atom_name", list_of(DataType::Utf8), false),
+        Field::new("res_name", list_of(DataType::Utf8), false),
+        Field::new("chain", list_of(DataType::Utf8), false),
+        Field::new("res_seq", list_of(DataType::Int32), false),
+        Field::new("residue_index", list_of(DataType::Int32), false),
+        Field::new("element", list_of(DataType::Utf8), false),
+        Field::new("coords", list_of(xyz), false),
+        Field::new("b_factor", list_of(DataType::Float32), false),
+    ]);
+    Schema::new(fields)
+}
+
+/// One-letter sequence of the polymer (ATOM) residues, with `/` between
+/// chains.
+pub(crate) fn sequence(atoms: &[Atom]) -> String {
+    let mut seq = String::new();
+    let mut last: Option<(char, i64, char)> = None;
+    for atom in atoms.iter().filter(|atom| !atom.hetatm) {
+        let key = (atom.chain, atom.res_seq, atom.i_code);
+        if last == Some(key) {
+            continue;
+        }
+        if last.is_some_and(|(chain, _, _)| chain != atom.chain) {
+            seq.push('/');
+        }
+        seq.push(one_letter(&atom.res_name));
+        last = Some(key);
+    }
+    seq
+}
+
+/// Builders for the per-atom list columns.
+struct AtomColumns {
+    atom_name: ListBuilder<StringBuilder>,
+    res_name: ListBuilder<StringBuilder>,
+    chain: ListBuilder<StringBuilder>,
+    res_seq: ListBuilder<Int32Builder>,
+    residue_index: ListBuilder<Int32Builder>,
+    element: ListBuilder<StringBuilder>,
+    coords: ListBuilder<FixedSizeListBuilder<Float32Builder>>,
+    b_factor: ListBuilder<Float32Builder>,
+}
+
+impl AtomColumns {
+    fn new() -> Self {
+        AtomColumns {
+            atom_name: ListBuilder::new(StringBuilder::new()),
+            res_name: ListBuilder::new(StringBuilder::new()),
+            chain: ListBuilder::new(StringBuilder::new()),
+            res_seq: ListBuilder::new(Int32Builder::new()),
+            residue_index: ListBuilder::new(Int32Builder::new()),
+            element: ListBuilder::new(StringBuilder::new()),
+            coords: ListBuilder::new(FixedSizeListBuilder::new(Float32Builder::new(), 3)),
+            b_factor: ListBuilder::new(Float32Builder::new()),
+        }
+    }
+
+    /// Add one row. Residues are indexed from 0 in file order, counting
+    /// every change of chain, number or insertion code.
+    fn append(&mut self, atoms: &[Atom]) {
+        let mut residue_index = -1;
+        let mut last: Option<(char, i64, char)> = None;
+        let mut chain = [0u8; 4];
+        for atom in atoms {
+            let key = (atom.chain, atom.res_seq, atom.i_code);
+            if last != Some(key) {
+                residue_index += 1;
+                last = Some(key);
+            }
+            self.atom_name.values().append_value(&atom.name);
+            self.res_name.values().append_value(&atom.res_name);
+            self.chain.values().append_value(atom.chain.encode_utf8(&mut chain));
+            self.res_seq.values().append_value(atom.res_seq as i32);
+            self.residue_index.values().append_value(residue_index);
+            self.element.values().append_value(&atom.element);
+            let xyz = self.coords.values();
+            for coord in [atom.x, atom.y, atom.z] {
+                xyz.values().append_value(coord as f32);
+            }
+            xyz.append(true);
+            self.b_factor.values().append_value(atom.b_factor as f32);
+        }
+        self.atom_name.append(true);
+        self.res_name.append(true);
+        self.chain.append(true);
+        self.res_seq.append(true);
+        self.residue_index.append(true);
+        self.element.append(true);
+        self.coords.append(true);
+        self.b_factor.append(true);
+    }
+
+    fn finish(mut self) -> [ArrayRef; 8] {
+        [
+            Arc::new(self.atom_name.finish()),
+            Arc::new(self.res_name.finish()),
+            Arc::new(self.chain.finish()),
+            Arc::new(self.res_seq.finish()),
+            Arc::new(self.residue_index.finish()),
+            Arc::new(self.element.finish()),
+            Arc::new(self.coords.finish()),
+            Arc::new(self.b_factor.finish()),
+        ]
+    }
+}
+
+/// Streaming reader of record batches over a Quiver file; see
+/// [`QuiverCore::structure_batches`].
+pub struct StructureBatches {
+    records: QuiverRecords,
+    tags: Option<HashSet<String>>,
+    selection: Option<Selection>,
+    scores: Vec<ScoreColumn>,
+    score_index: HashMap<String, usize>,
+    schema: SchemaRef,
+    batch_size: usize,
+}
+
+impl StructureBatches {
+    fn build(&self, records: &[QuiverRecord]) -> Result<RecordBatch, ArrowError> {
+        let mut tags = StringBuilder::new();
+        let mut sequences = StringBuilder::new();
+        let mut scores: Vec<Vec<Option<String>>> = vec![Vec::with_capacity(records.len()); self.scores.len()];
+        let mut atoms = AtomColumns::new();
+        for record in records {
+            tags.append_value(&record.tag);
+            let mut values = vec![None; self.scores.len()];
+            for (key, value) in record.score.as_deref().map(parse_score_str).unwrap_or_default() {
+                if let Some(&col) = self.score_index.get(&key) {
+                    values[col] = Some(value);
+                }
+            }
+            for (column, value) in scores.iter_mut().zip(values) {
+                column.push(value);
+            }
+            let parsed = match &self.selection {
+                Some(selection) => parse_atoms(&selection.filter_lines(&record.lines)),
+                None => parse_atoms(&record.lines),
+            };
+            sequences.append_value(sequence(&parsed));
+            atoms.append(&parsed);
+        }
+
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(tags.finish())];
+        if !self.scores.is_empty() {
+            let DataType::Struct(fields) = self.schema.field_with_name("scores")?.data_type().clone() else {
+                unreachable!("scores is a struct column");
+            };
+            let arrays: Vec<ArrayRef> = scores
+                .into_iter()
+                .zip(&self.scores)
+                .map(|(values, column)| -> ArrayRef {
+                    if column.numeric {
+                        Arc::new(values.iter().map(|v| v.as_deref().and_then(|v| v.parse().ok())).collect::<Float64Array>())
+                    } else {
+                        Arc::new(values.iter().map(|v| v.as_deref()).collect::<StringArray>())
+                    }
+                })
+                .collect();
+            columns.push(Arc::new(StructArray::try_new(fields, arrays, None)?));
+        }
+        columns.push(Arc::new(sequences.finish()));
+        columns.extend(atoms.finish());
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+
+    /// Write all remaining batches to a Parquet file. Returns the number
+    /// of rows written.
+    pub fn write_parquet(self, path: &str) -> Result<usize> {
+        let file = File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, self.schema.clone(), None)?;
+        let mut rows = 0;
+        for batch in self {
+            let batch = batch?;
+            rows += batch.num_rows();
+            writer.write(&batch)?;
+        }
+        writer.close()?;
+        Ok(rows)
+    }
+}
+
+impl Iterator for StructureBatches {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut records = Vec::with_capacity(self.batch_size);
+        while records.len() < self.batch_size {
+            match self.records.next() {
+                None => break,
+                Some(Err(e)) => return Some(Err(ArrowError::ExternalError(Box::new(e)))),
+                Some(Ok(record)) => {
+                    if self.tags.as_ref().is_none_or(|tags| tags.contains(&record.tag)) {
+                        records.push(record);
+                    }
+                }
+            }
+        }
+        if records.is_empty() {
+            return None;
+        }
+        Some(self.build(&records))
+    }
+}
+
+impl RecordBatchReader for StructureBatches {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl QuiverCore {
+    /// Score terms of the records in `tags` (all records if `None`), in
+    /// order of first appearance. Only `QV_TAG` and `QV_SCORE` lines are
+    /// decoded.
+    fn score_columns(&self, tags: Option<&HashSet<String>>) -> Result<Vec<ScoreColumn>> {
+        let mut reader = BufReader::new(File::open(&self.fnm)?);
+        let mut columns: Vec<ScoreColumn> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut included = true;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            if buf.starts_with(b"QV_TAG") {
+                let line = String::from_utf8_lossy(&buf);
+                let tag = line.split_whitespace().nth(1).unwrap_or("");
+                included = tags.is_none_or(|tags| tags.contains(tag));
+            } else if included && buf.starts_with(b"QV_SCORE") {
+                let line = String::from_utf8_lossy(&buf);
+                let Some(score) = line.split_whitespace().nth(2) else { continue };
+                for (key, value) in parse_score_str(score) {
+                    let col = *index.entry(key.clone()).or_insert_with(|| {
+                        columns.push(ScoreColumn { name: key, numeric: true });
+                        columns.len() - 1
+                    });
+                    columns[col].numeric &= value.parse::<f64>().is_ok();
+                }
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Stream the records as Arrow record batches of up to `batch_size`
+    /// rows, in file order. With `tags`, only those records are included;
+    /// every tag must exist. With `selection`, only matching atoms are kept.
+    pub fn structure_batches(
+        &self,
+        tags: Option<&[String]>,
+        selection: Option<&Selection>,
+        batch_size: usize,
+    ) -> Result<StructureBatches> {
+        if batch_size == 0 {
+            return Err(QuiverError::Invalid("batch_size must be a positive integer.".to_string()));
+        }
+        let tags: Option<HashSet<String>> = match tags {
+            Some(tags) => {
+                let present: HashSet<&String> = self.tags.iter().collect();
+                if let Some(missing) = tags.iter().find(|tag| !present.contains(tag)) {
+                    return Err(QuiverError::TagNotFound(missing.clone()));
+                }
+                Some(tags.iter().cloned().collect())
+            }
+            None => None,
+        };
+        let scores = self.score_columns(tags.as_ref())?;
+        let score_index = scores.iter().enumerate().map(|(i, column)| (column.name.clone(), i)).collect();
+        Ok(StructureBatches {
+            records: self.records()?,
+            tags,
+            selection: selection.cloned(),
+            schema: Arc::new(schema(&scores)),
+            scores,
+            score_index,
+            batch_size,
+        })
+    }
+}
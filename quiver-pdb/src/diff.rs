@@ -0,0 +1,140 @@
+//! Record-by-record comparison of two Quiver files (`qvdiff`).
+
+use crate::pdb::{parse_atoms, Atom};
+use crate::score::parse_score_str;
+use crate::{QuiverCore, QuiverRecord, Result};
+use std::collections::{HashMap, HashSet};
+
+/// How the structure of a tag present in both files differs.
+#[derive(Debug, Clone)]
+pub struct StructureDiff {
+    pub tag: String,
+    /// Coordinate RMSD over matching atoms, without superposition; `None`
+    /// when the two records do not contain the same atoms.
+    pub rmsd: Option<f64>,
+}
+
+/// A score term of one tag whose value differs; `None` when the term is
+/// missing on that side.
+#[derive(Debug, Clone)]
+pub struct ScoreDiff {
+    pub tag: String,
+    pub term: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveDiff {
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    pub structures: Vec<StructureDiff>,
+    pub scores: Vec<ScoreDiff>,
+}
+
+impl ArchiveDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_a.is_empty() && self.only_b.is_empty() && self.structures.is_empty() && self.scores.is_empty()
+    }
+}
+
+/// PDB lines without line endings and trailing `END` or blank lines, so
+/// records differing only in those compare equal.
+fn content_lines(record: &QuiverRecord) -> Vec<&str> {
+    let mut lines: Vec<&str> = record.lines.iter().map(|line| line.trim_end_matches(['\n', '\r'])).collect();
+    while lines.last().is_some_and(|line| line.trim().is_empty() || line.trim() == "END") {
+        lines.pop();
+    }
+    lines
+}
+
+/// RMSD between atoms matched by chain, residue, insertion code, atom name
+/// and alternate location, or `None` if the atom sets differ.
+fn coordinate_rmsd(a: &QuiverRecord, b: &QuiverRecord) -> Option<f64> {
+    let key = |atom: &Atom| (atom.chain, atom.res_seq, atom.i_code, atom.name.clone(), atom.alt_loc);
+    let (atoms_a, atoms_b) = (parse_atoms(&a.lines), parse_atoms(&b.lines));
+    if atoms_a.len() != atoms_b.len() || atoms_a.is_empty() {
+        return None;
+    }
+    let index: HashMap<_, usize> = atoms_b.iter().enumerate().map(|(i, atom)| (key(atom), i)).collect();
+    let mut sum = 0.0;
+    for atom in &atoms_a {
+        let other = &atoms_b[*index.get(&key(atom))?];
+        sum += (atom.x - other.x).powi(2) + (atom.y - other.y).powi(2) + (atom.z - other.z).powi(2);
+    }
+    Some((sum / atoms_a.len() as f64).sqrt())
+}
+
+/// Values equal as text or as numbers, so `1.5` and `1.50` match.
+fn same_value(a: &str, b: &str) -> bool {
+    a == b || matches!((a.parse::<f64>(), b.parse::<f64>()), (Ok(x), Ok(y)) if x == y)
+}
+
+fn score_diffs(tag: &str, a: &QuiverRecord, b: &QuiverRecord) -> Vec<ScoreDiff> {
+    let parse = |record: &QuiverRecord| record.score.as_deref().map(parse_score_str).unwrap_or_default();
+    let (terms_a, terms_b) = (parse(a), parse(b));
+    let values_b: HashMap<&str, &str> = terms_b.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let mut diffs = Vec::new();
+    for (term, value) in &terms_a {
+        let other = values_b.get(term.as_str()).copied();
+        if !other.is_some_and(|other| same_value(value, other)) {
+            diffs.push(ScoreDiff {
+                tag: tag.to_string(),
+                term: term.clone(),
+                a: Some(value.clone()),
+                b: other.map(String::from),
+            });
+        }
+    }
+    let names_a: HashSet<&str> = terms_a.iter().map(|(k, _)| k.as_str()).collect();
+    for (term, value) in terms_b.iter().filter(|(k, _)| !names_a.contains(k.as_str())) {
+        diffs.push(ScoreDiff { tag: tag.to_string(), term: term.clone(), a: None, b: Some(value.clone()) });
+    }
+    diffs
+}
+
+impl QuiverCore {
+    /// Compare this file (A) against `other` (B), tag by tag in the order of
+    /// A. Records whose PDB text differs are reported; with
+    /// `rmsd_tolerance`, only those whose atoms differ or whose coordinate
+    /// RMSD exceeds the tolerance are. Score terms are compared when
+    /// `compare_scores` is set.
+    pub fn diff(
+        &self,
+        other: &QuiverCore,
+        rmsd_tolerance: Option<f64>,
+        compare_scores: bool,
+    ) -> Result<ArchiveDiff> {
+        let tags_a: HashSet<&String> = self.tags.iter().collect();
+        let tags_b: HashSet<&String> = other.tags.iter().collect();
+        let mut only_b: Vec<String> = other.tags.iter().filter(|tag| !tags_a.contains(tag)).cloned().collect();
+        let mut unique = HashSet::new();
+        only_b.retain(|tag| unique.insert(tag.clone()));
+        let mut diff = ArchiveDiff { only_b, ..Default::default() };
+
+        let mut seen = HashSet::new();
+        for record in self.records()? {
+            let record = record?;
+            // Like the reader, the first record of a duplicated tag counts.
+            if !seen.insert(record.tag.clone()) {
+                continue;
+            }
+            if !tags_b.contains(&record.tag) {
+                diff.only_a.push(record.tag);
+                continue;
+            }
+            let theirs = other.get_record(&record.tag)?;
+            if content_lines(&record) != content_lines(&theirs) {
+                let rmsd = coordinate_rmsd(&record, &theirs);
+                let within = matches!((rmsd, rmsd_tolerance), (Some(rmsd), Some(tol)) if rmsd <= tol);
+                if !within {
+                    diff.structures.push(StructureDiff { tag: record.tag.clone(), rmsd });
+                }
+            }
+            if compare_scores {
+                diff.scores.extend(score_diffs(&record.tag, &record, &theirs));
+            }
+        }
+        Ok(diff)
+    }
+}
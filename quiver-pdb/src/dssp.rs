@@ -0,0 +1,352 @@
+//! DSSP-style secondary structure assignment from backbone hydrogen bonds.
+//!
+//! Follows Kabsch & Sander (1983): amide hydrogens are placed from the
+//! preceding C=O, backbone H-bonds are those with an electrostatic energy
+//! below -0.5 kcal/mol, and helices, bridges, ladders, turns and bends are
+//! derived from the H-bond pattern. Per-residue codes are the DSSP letters
+//! `H G I E B T S`, with `-` for loop. Only the energy cutoff is applied
+//! (DSSP also keeps just the two best bonds per N-H and C=O), so rare
+//! bifurcated cases can differ from mkdssp.
+
+use crate::pdb::{parse_atoms, Atom};
+use crate::scorefile::ScoreTable;
+use crate::{QuiverCore, Result};
+use rayon::prelude::*;
+
+type Vec3 = [f64; 3];
+
+/// Column names of [`SecondaryStructure::to_pairs`], in order.
+pub const DSSP_TERMS: [&str; 4] = ["dssp", "helix", "strand", "loop"];
+
+/// H-bond energy cutoff (kcal/mol).
+const HBOND_ENERGY: f64 = -0.5;
+/// Longest C–N distance (Å) between residues not counted as a chain break.
+const MAX_PEPTIDE_BOND: f64 = 2.5;
+/// CA–CA distance (Å) beyond which no H-bond is possible.
+const MAX_CA_DISTANCE: f64 = 9.0;
+/// Smallest CA(i-2)–CA(i)–CA(i+2) bend angle (degrees) marked `S`.
+const MIN_BEND_ANGLE: f64 = 70.0;
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: Vec3) -> f64 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+fn distance(a: Vec3, b: Vec3) -> f64 {
+    norm(sub(a, b))
+}
+
+/// Backbone of one residue; residues missing any of N, CA, C, O take no
+/// part in H-bonds.
+struct Residue {
+    chain: char,
+    proline: bool,
+    n: Option<Vec3>,
+    ca: Option<Vec3>,
+    c: Option<Vec3>,
+    o: Option<Vec3>,
+    h: Option<Vec3>,
+    /// No peptide bond to the previous residue.
+    break_before: bool,
+}
+
+fn residues(atoms: &[Atom]) -> Vec<Residue> {
+    let mut residues: Vec<Residue> = Vec::new();
+    let mut last_key = None;
+    for atom in atoms.iter().filter(|a| !a.hetatm && matches!(a.alt_loc, ' ' | 'A')) {
+        let key = (atom.chain, atom.res_seq, atom.i_code);
+        if last_key != Some(key) {
+            residues.push(Residue {
+                chain: atom.chain,
+                proline: atom.res_name == "PRO",
+                n: None,
+                ca: None,
+                c: None,
+                o: None,
+                h: None,
+                break_before: true,
+            });
+            last_key = Some(key);
+        }
+        let Some(residue) = residues.last_mut() else { continue };
+        let xyz = Some([atom.x, atom.y, atom.z]);
+        match atom.name.as_str() {
+            "N" => residue.n = xyz,
+            "CA" => residue.ca = xyz,
+            "C" => residue.c = xyz,
+            "O" => residue.o = xyz,
+            _ => {}
+        }
+    }
+
+    for i in 1..residues.len() {
+        let (prev, next) = (&residues[i - 1], &residues[i]);
+        let bonded = prev.chain == next.chain
+            && matches!((prev.c, next.n), (Some(c), Some(n)) if distance(c, n) <= MAX_PEPTIDE_BOND);
+        residues[i].break_before = !bonded;
+        // H sits 1 Å from N, along the C=O direction of the previous residue.
+        if bonded && !residues[i].proline {
+            if let (Some(c), Some(o), Some(n)) = (residues[i - 1].c, residues[i - 1].o, residues[i].n) {
+                let co = sub(c, o);
+                let len = norm(co);
+                if len > 0.0 {
+                    residues[i].h = Some([n[0] + co[0] / len, n[1] + co[1] / len, n[2] + co[2] / len]);
+                }
+            }
+        }
+    }
+    residues
+}
+
+/// Electrostatic energy (kcal/mol) of the bond from the C=O of `acceptor`
+/// to the N-H of `donor`.
+fn hbond_energy(acceptor: &Residue, donor: &Residue) -> Option<f64> {
+    let (c, o) = (acceptor.c?, acceptor.o?);
+    let (n, h) = (donor.n?, donor.h?);
+    let (r_on, r_ch, r_oh, r_cn) = (distance(o, n), distance(c, h), distance(o, h), distance(c, n));
+    if r_on.min(r_ch).min(r_oh).min(r_cn) < 1e-3 {
+        return None;
+    }
+    Some(0.084 * 332.0 * (1.0 / r_on + 1.0 / r_ch - 1.0 / r_oh - 1.0 / r_cn))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BridgeKind {
+    Parallel,
+    Antiparallel,
+}
+
+/// A run of consecutive bridges between residues `i` and `j` (`i < j`).
+struct Ladder {
+    kind: BridgeKind,
+    i: (usize, usize),
+    j: (usize, usize),
+    bridges: usize,
+}
+
+/// Per-residue secondary structure of one record.
+#[derive(Debug, Clone, Default)]
+pub struct SecondaryStructure {
+    /// One DSSP code per polymer residue, in file order.
+    pub codes: String,
+}
+
+impl SecondaryStructure {
+    pub fn compute(atoms: &[Atom]) -> Self {
+        let residues = residues(atoms);
+        let n = residues.len();
+
+        // hbond[i] holds the donors j whose N-H bonds to the C=O of i.
+        let hbond: Vec<Vec<usize>> = (0..n)
+            .map(|i| {
+                let Some(ca_i) = residues[i].ca else { return Vec::new() };
+                (0..n)
+                    .filter(|&j| j != i && residues[j].ca.is_some_and(|ca_j| distance(ca_i, ca_j) < MAX_CA_DISTANCE))
+                    .filter(|&j| hbond_energy(&residues[i], &residues[j]).is_some_and(|e| e < HBOND_ENERGY))
+                    .collect()
+            })
+            .collect();
+        let bonded = |i: usize, j: usize| i < n && j < n && hbond[i].contains(&j);
+        // No chain break anywhere in residues a..=b.
+        let unbroken = |a: usize, b: usize| b < n && ((a + 1)..=b).all(|k| !residues[k].break_before);
+
+        let mut codes = vec!['-'; n];
+
+        // Bends.
+        for i in 2..n.saturating_sub(2) {
+            if !unbroken(i - 2, i + 2) {
+                continue;
+            }
+            if let (Some(a), Some(b), Some(c)) = (residues[i - 2].ca, residues[i].ca, residues[i + 2].ca) {
+                let (u, v) = (sub(b, a), sub(c, b));
+                let cos = (u[0] * v[0] + u[1] * v[1] + u[2] * v[2]) / (norm(u) * norm(v));
+                if cos.clamp(-1.0, 1.0).acos().to_degrees() > MIN_BEND_ANGLE {
+                    codes[i] = 'S';
+                }
+            }
+        }
+
+        // n-turns: the C=O of i bonds to the N-H of i+n.
+        let turn = |i: usize, len: usize| unbroken(i, i + len) && bonded(i, i + len);
+        for len in [3, 4, 5] {
+            for i in 0..n {
+                if turn(i, len) {
+                    for code in &mut codes[(i + 1)..(i + len)] {
+                        *code = 'T';
+                    }
+                }
+            }
+        }
+
+        // Helices from two consecutive n-turns, lowest priority first.
+        for (len, code) in [(5, 'I'), (3, 'G')] {
+            for i in 1..n {
+                if turn(i - 1, len) && turn(i, len) {
+                    for c in &mut codes[i..(i + len)] {
+                        *c = code;
+                    }
+                }
+            }
+        }
+
+        // Bridges and ladders.
+        let mut bridges: Vec<(usize, usize, BridgeKind)> = Vec::new();
+        for i in 1..n.saturating_sub(1) {
+            for j in (i + 3)..n.saturating_sub(1) {
+                if !unbroken(i - 1, i + 1) || !unbroken(j - 1, j + 1) {
+                    continue;
+                }
+                if (bonded(i - 1, j) && bonded(j, i + 1)) || (bonded(j - 1, i) && bonded(i, j + 1)) {
+                    bridges.push((i, j, BridgeKind::Parallel));
+                } else if (bonded(i, j) && bonded(j, i)) || (bonded(i - 1, j + 1) && bonded(j - 1, i + 1)) {
+                    bridges.push((i, j, BridgeKind::Antiparallel));
+                }
+            }
+        }
+        let mut ladders: Vec<Ladder> = Vec::new();
+        for &(i, j, kind) in &bridges {
+            let extends = ladders.iter_mut().find(|l| {
+                l.kind == kind
+                    && l.i.1 + 1 == i
+                    && match kind {
+                        BridgeKind::Parallel => l.j.1 + 1 == j,
+                        BridgeKind::Antiparallel => l.j.0 == j + 1,
+                    }
+            });
+            match extends {
+                Some(ladder) => {
+                    ladder.i.1 = i;
+                    match kind {
+                        BridgeKind::Parallel => ladder.j.1 = j,
+                        BridgeKind::Antiparallel => ladder.j.0 = j,
+                    }
+                    ladder.bridges += 1;
+                }
+                None => ladders.push(Ladder { kind, i: (i, i), j: (j, j), bridges: 1 }),
+            }
+        }
+        // β-bulges: join ladders of one kind separated by at most one extra
+        // residue on one strand and four on the other.
+        let mut merged = true;
+        while merged {
+            merged = false;
+            'outer: for a in 0..ladders.len() {
+                for b in 0..ladders.len() {
+                    let (la, lb) = (&ladders[a], &ladders[b]);
+                    if a == b || la.kind != lb.kind || lb.i.0 <= la.i.1 {
+                        continue;
+                    }
+                    let gap_i = lb.i.0 - la.i.1;
+                    let gap_j = match la.kind {
+                        BridgeKind::Parallel => lb.j.0 as i64 - la.j.1 as i64,
+                        BridgeKind::Antiparallel => la.j.0 as i64 - lb.j.1 as i64,
+                    };
+                    if gap_j <= 0 {
+                        continue;
+                    }
+                    let gap_j = gap_j as usize;
+                    if (gap_i <= 2 && gap_j <= 5) || (gap_i <= 5 && gap_j <= 2) {
+                        let lb = ladders.remove(b);
+                        let la = &mut ladders[if b < a { a - 1 } else { a }];
+                        la.i = (la.i.0, lb.i.1);
+                        la.j = (la.j.0.min(lb.j.0), la.j.1.max(lb.j.1));
+                        la.bridges += lb.bridges;
+                        merged = true;
+                        break 'outer;
+                    }
+                }
+            }
+        }
+        // As in mkdssp, an isolated bridge never replaces a ladder residue.
+        for ladder in &ladders {
+            let code = if ladder.bridges > 1 { 'E' } else { 'B' };
+            for k in (ladder.i.0..=ladder.i.1).chain(ladder.j.0..=ladder.j.1) {
+                if code == 'E' || codes[k] != 'E' {
+                    codes[k] = code;
+                }
+            }
+        }
+
+        // α-helices take precedence over everything else.
+        for i in 1..n {
+            if turn(i - 1, 4) && turn(i, 4) {
+                for c in &mut codes[i..(i + 4)] {
+                    *c = 'H';
+                }
+            }
+        }
+
+        SecondaryStructure { codes: codes.into_iter().collect() }
+    }
+
+    fn fraction(&self, set: &[char]) -> f64 {
+        let total = self.codes.chars().count();
+        if total == 0 {
+            return 0.0;
+        }
+        self.codes.chars().filter(|c| set.contains(c)).count() as f64 / total as f64
+    }
+
+    /// Fraction of residues in helices (`H`, `G`, `I`).
+    pub fn helix(&self) -> f64 {
+        self.fraction(&['H', 'G', 'I'])
+    }
+
+    /// Fraction of residues in strands or bridges (`E`, `B`).
+    pub fn strand(&self) -> f64 {
+        self.fraction(&['E', 'B'])
+    }
+
+    /// Fraction of residues in neither.
+    pub fn loop_fraction(&self) -> f64 {
+        if self.codes.is_empty() {
+            return 0.0;
+        }
+        1.0 - self.helix() - self.strand()
+    }
+
+    /// The assignment as score terms, named as in [`DSSP_TERMS`].
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let values = [
+            self.codes.clone(),
+            format!("{:.3}", self.helix()),
+            format!("{:.3}", self.strand()),
+            format!("{:.3}", self.loop_fraction()),
+        ];
+        DSSP_TERMS.iter().map(|term| term.to_string()).zip(values).collect()
+    }
+}
+
+impl QuiverCore {
+    /// Secondary structure of every record, in file order.
+    pub fn secondary_structure(&self) -> Result<Vec<(String, SecondaryStructure)>> {
+        let mut records = Vec::with_capacity(self.tags.len());
+        for record in self.records()? {
+            let record = record?;
+            records.push((record.tag, parse_atoms(&record.lines)));
+        }
+        Ok(records
+            .into_par_iter()
+            .map(|(tag, atoms)| (tag, SecondaryStructure::compute(&atoms)))
+            .collect())
+    }
+}
+
+/// A table with one row per record: the DSSP string and the helix, strand
+/// and loop fractions.
+pub fn dssp_table(assignments: &[(String, SecondaryStructure)]) -> ScoreTable {
+    let mut columns = vec!["tag".to_string()];
+    columns.extend(DSSP_TERMS.iter().map(|term| term.to_string()));
+    let rows = assignments
+        .iter()
+        .map(|(tag, ss)| {
+            std::iter::once(Some(tag.clone()))
+                .chain(ss.to_pairs().into_iter().map(|(_, value)| Some(value)))
+                .collect()
+        })
+        .collect();
+    ScoreTable { columns, rows }
+}
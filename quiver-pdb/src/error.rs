@@ -0,0 +1,79 @@
+//! Error type of the reader and writer API.
+
+use arrow_schema::ArrowError;
+use parquet::errors::ParquetError;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum QuiverError {
+    /// Reading or writing the underlying file failed.
+    Io(io::Error),
+    /// The file was opened in a mode that does not allow the operation.
+    Mode(String),
+    /// No record has the requested tag.
+    TagNotFound(String),
+    /// A record with this tag already exists.
+    DuplicateTag(String),
+    /// Malformed file content or an invalid argument.
+    Invalid(String),
+    /// Building Arrow record batches failed.
+    Arrow(ArrowError),
+    /// Writing a Parquet file failed.
+    Parquet(ParquetError),
+}
+
+/// `Result` with [`QuiverError`] as the default error type.
+pub type Result<T, E = QuiverError> = std::result::Result<T, E>;
+
+impl QuiverError {
+    pub(crate) fn read_mode() -> Self {
+        QuiverError::Mode("Quiver file must be opened in read mode to allow for reading.".to_string())
+    }
+
+    pub(crate) fn write_mode() -> Self {
+        QuiverError::Mode("Quiver file must be opened in write mode to allow for writing.".to_string())
+    }
+}
+
+impl fmt::Display for QuiverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuiverError::Io(e) => write!(f, "{}", e),
+            QuiverError::Arrow(e) => write!(f, "{}", e),
+            QuiverError::Parquet(e) => write!(f, "{}", e),
+            QuiverError::Mode(msg) | QuiverError::Invalid(msg) => write!(f, "{}", msg),
+            QuiverError::TagNotFound(tag) => write!(f, "Requested tag: {} does not exist", tag),
+            QuiverError::DuplicateTag(tag) => write!(f, "Tag {} already exists in this file.", tag),
+        }
+    }
+}
+
+impl std::error::Error for QuiverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QuiverError::Io(e) => Some(e),
+            QuiverError::Arrow(e) => Some(e),
+            QuiverError::Parquet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for QuiverError {
+    fn from(e: io::Error) -> Self {
+        QuiverError::Io(e)
+    }
+}
+
+impl From<ArrowError> for QuiverError {
+    fn from(e: ArrowError) -> Self {
+        QuiverError::Arrow(e)
+    }
+}
+
+impl From<ParquetError> for QuiverError {
+    fn from(e: ParquetError) -> Self {
+        QuiverError::Parquet(e)
+    }
+}
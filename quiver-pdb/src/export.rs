@@ -0,0 +1,165 @@
+//! Export modes that write many Quiver records into a single output.
+
+use crate::pdb::parse_atoms;
+use crate::select::Selection;
+use crate::{QuiverCore, QuiverRecord, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Record types that describe model boundaries and are rewritten on export.
+fn is_model_delimiter(line: &str) -> bool {
+    line.starts_with("MODEL") || line.starts_with("ENDMDL") || line.trim_end() == "END"
+}
+
+/// The record with only the atoms in `selection`, or unchanged without one.
+fn selected(mut record: QuiverRecord, selection: Option<&Selection>) -> QuiverRecord {
+    if let Some(selection) = selection {
+        record.lines = selection.filter_lines(&record.lines);
+    }
+    record
+}
+
+fn write_cif_record<W: Write>(out: &mut W, record: &QuiverRecord) -> Result<()> {
+    let atoms = parse_atoms(&record.lines);
+    let cif_value = |s: &str| if s.is_empty() { "?".to_string() } else { s.to_string() };
+    let cif_char = |c: char| if c == ' ' { ".".to_string() } else { c.to_string() };
+
+    let mut text = String::new();
+    text.push_str(&format!("data_{}\n#\n_entry.id {}\n#\n", record.tag, record.tag));
+    text.push_str("loop_\n");
+    for field in [
+        "group_PDB",
+        "id",
+        "type_symbol",
+        "label_atom_id",
+        "label_alt_id",
+        "label_comp_id",
+        "label_asym_id",
+        "label_seq_id",
+        "pdbx_PDB_ins_code",
+        "Cartn_x",
+        "Cartn_y",
+        "Cartn_z",
+        "occupancy",
+        "B_iso_or_equiv",
+        "auth_seq_id",
+        "auth_asym_id",
+        "pdbx_PDB_model_num",
+    ] {
+        text.push_str("_atom_site.");
+        text.push_str(field);
+        text.push('\n');
+    }
+    for atom in &atoms {
+        // Atom names such as O5' or "O5'" must be quoted in CIF.
+        let atom_name = if atom.name.contains('\'') {
+            format!("\"{}\"", atom.name)
+        } else {
+            atom.name.clone()
+        };
+        text.push_str(&format!(
+            "{} {} {} {} {} {} {} {} {} {:.3} {:.3} {:.3} {:.2} {:.2} {} {} 1\n",
+            if atom.hetatm { "HETATM" } else { "ATOM" },
+            atom.serial,
+            cif_value(&atom.element),
+            atom_name,
+            cif_char(atom.alt_loc),
+            cif_value(&atom.res_name),
+            cif_char(atom.chain),
+            atom.res_seq,
+            cif_char(atom.i_code),
+            atom.x,
+            atom.y,
+            atom.z,
+            atom.occupancy,
+            atom.b_factor,
+            atom.res_seq,
+            cif_char(atom.chain),
+        ));
+    }
+    text.push_str("#\n");
+    Ok(out.write_all(text.as_bytes())?)
+}
+
+fn write_tar_entries<W: Write>(qv: &QuiverCore, out: W, selection: Option<&Selection>) -> Result<(usize, W)> {
+    let mut builder = tar::Builder::new(out);
+    let mut count = 0;
+    for record in qv.records()? {
+        let record = selected(record?, selection);
+        let data = record.lines.concat();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{}.pdb", record.tag), data.as_bytes())
+            ?;
+        count += 1;
+    }
+    let out = builder.into_inner()?;
+    Ok((count, out))
+}
+
+impl QuiverCore {
+    /// Write every record into one multi-MODEL PDB file. The tag of each
+    /// model is listed in REMARK lines ahead of the first MODEL, since
+    /// viewers ignore records between MODEL and ENDMDL other than atoms.
+    /// With `selection`, only the selected atoms of each model are written.
+    /// Returns the number of models written.
+    pub fn write_multimodel_pdb(&self, outfn: &str, selection: Option<&Selection>) -> Result<usize> {
+        let mut out = BufWriter::new(File::create(outfn)?);
+        for (i, tag) in self.tags.iter().enumerate() {
+            writeln!(out, "REMARK   1 MODEL {:>4} QV_TAG {}", i + 1, tag)?;
+        }
+        let mut count = 0;
+        for record in self.records()? {
+            let record = selected(record?, selection);
+            count += 1;
+            writeln!(out, "MODEL     {:>4}", count)?;
+            for line in record.lines.iter().filter(|l| !is_model_delimiter(l)) {
+                out.write_all(line.as_bytes())?;
+            }
+            writeln!(out, "ENDMDL")?;
+        }
+        writeln!(out, "END")?;
+        out.flush()?;
+        Ok(count)
+    }
+
+    /// Write each record as `<tag>.cif` inside `outdir`. Returns the number of
+    /// files written.
+    pub fn write_mmcif(&self, outdir: &str, selection: Option<&Selection>) -> Result<usize> {
+        std::fs::create_dir_all(outdir)?;
+        let mut count = 0;
+        for record in self.records()? {
+            let record = selected(record?, selection);
+            let out_path = Path::new(outdir).join(format!("{}.cif", record.tag));
+            let mut out = BufWriter::new(File::create(out_path)?);
+            write_cif_record(&mut out, &record)?;
+            out.flush()?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Write every record as `<tag>.pdb` into a tar archive. The archive is
+    /// gzip-compressed when `outfn` ends in `.gz` or `.tgz`. Returns the number
+    /// of entries written.
+    pub fn write_tar(&self, outfn: &str, selection: Option<&Selection>) -> Result<usize> {
+        let out = BufWriter::new(File::create(outfn)?);
+        let count = if outfn.ends_with(".gz") || outfn.ends_with(".tgz") {
+            let (count, encoder) = write_tar_entries(self, GzEncoder::new(out, Compression::default()), selection)?;
+            let mut out = encoder.finish()?;
+            out.flush()?;
+            count
+        } else {
+            let (count, mut out) = write_tar_entries(self, out, selection)?;
+            out.flush()?;
+            count
+        };
+        Ok(count)
+    }
+}
@@ -0,0 +1,80 @@
+//! Selecting records by conditions on their `QV_SCORE` terms.
+
+use crate::score::score_value;
+use crate::{QuiverCore, QuiverError, RecordOffset, Result};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// A condition such as `ddg<-10` or `plddt>=85`. Records lacking the term,
+/// or whose value is not a number, never match.
+#[derive(Debug, Clone)]
+pub struct ScoreCondition {
+    pub term: String,
+    op: Op,
+    pub value: f64,
+}
+
+impl ScoreCondition {
+    pub fn parse(spec: &str) -> Result<Self> {
+        // Two-character operators first, so `<=` is not read as `<`.
+        const OPS: [(&str, Op); 6] =
+            [("<=", Op::Le), (">=", Op::Ge), ("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt)];
+        let invalid = || QuiverError::Invalid(format!("Condition must look like 'term<value' (<, <=, >, >=, ==, !=), not '{}'", spec));
+        let (pos, symbol, op) = OPS
+            .iter()
+            .filter_map(|&(symbol, op)| spec.find(symbol).map(|pos| (pos, symbol, op)))
+            .min_by_key(|&(pos, symbol, _)| (pos, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(invalid)?;
+        let term = spec[..pos].trim();
+        let value = spec[pos + symbol.len()..].trim();
+        if term.is_empty() {
+            return Err(invalid());
+        }
+        let value = value.parse().map_err(|_| QuiverError::Invalid(format!("'{}' in condition '{}' is not a number", value, spec)))?;
+        Ok(ScoreCondition { term: term.to_string(), op, value })
+    }
+
+    pub fn matches(&self, score: Option<&str>) -> bool {
+        let Some(x) = score.and_then(|score| score_value(score, &self.term)) else {
+            return false;
+        };
+        match self.op {
+            Op::Lt => x < self.value,
+            Op::Le => x <= self.value,
+            Op::Gt => x > self.value,
+            Op::Ge => x >= self.value,
+            Op::Eq => x == self.value,
+            Op::Ne => x != self.value,
+        }
+    }
+}
+
+impl QuiverCore {
+    /// Write the records meeting every condition to `out`, in file order.
+    /// Returns the number of records written.
+    pub fn filter_records<W: Write>(&self, conditions: &[ScoreCondition], out: &mut W) -> Result<usize> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
+        }
+        let offsets = self.record_offsets()?;
+        let mut kept: Vec<RecordOffset> = Vec::new();
+        for (record, offset) in self.records()?.zip(offsets) {
+            let record = record?;
+            if conditions.iter().all(|condition| condition.matches(record.score.as_deref())) {
+                kept.push(offset);
+            }
+        }
+        self.write_header(out)?;
+        self.copy_records(&kept, out)?;
+        Ok(kept.len())
+    }
+}
@@ -0,0 +1,104 @@
+//! The optional `QV_HEADER` block at the start of a Quiver file.
+//!
+//! Each header line holds one `key=value` entry in the score encoding:
+//!
+//! ```text
+//! QV_HEADER format_version=1
+//! QV_HEADER tool=quiver-pdb
+//! QV_HEADER created=2026-01-01T00:00:00Z
+//! ```
+//!
+//! Files without a header are format version 0 and still load.
+
+use crate::score::{encode_score, parse_score_str, SCORE_ENCODING_VERSION};
+use crate::Result;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current Quiver file format version written into new headers.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default)]
+pub struct QuiverHeader {
+    pub entries: Vec<(String, String)>,
+}
+
+/// Current UTC time as an ISO 8601 timestamp (`YYYY-MM-DDTHH:MM:SSZ`).
+pub(crate) fn utc_timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil date from days since 1970-01-01 (H. Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+impl QuiverHeader {
+    /// A header for a newly created file, followed by caller metadata.
+    /// Caller entries override the standard ones with the same key.
+    pub fn for_new_file(metadata: &[(String, String)]) -> Self {
+        let mut header = QuiverHeader {
+            entries: vec![
+                ("format_version".to_string(), FORMAT_VERSION.to_string()),
+                ("tool".to_string(), env!("CARGO_PKG_NAME").to_string()),
+                ("tool_version".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+                ("created".to_string(), utc_timestamp()),
+                ("score_encoding".to_string(), SCORE_ENCODING_VERSION.to_string()),
+            ],
+        };
+        for (key, value) in metadata {
+            header.set(key, value);
+        }
+        header
+    }
+
+    /// Read the leading header block of a file, if it has one.
+    pub fn read(filename: &str) -> Result<Option<Self>> {
+        if !Path::new(filename).exists() {
+            return Ok(None);
+        }
+        let file = File::open(filename)?;
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let Some(rest) = line.strip_prefix("QV_HEADER") else { break };
+            entries.extend(parse_score_str(rest.trim()));
+        }
+        Ok(if entries.is_empty() { None } else { Some(QuiverHeader { entries }) })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        for entry in &self.entries {
+            writeln!(out, "QV_HEADER {}", encode_score(std::slice::from_ref(entry)))?;
+        }
+        Ok(())
+    }
+}
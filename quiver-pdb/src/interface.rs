@@ -0,0 +1,231 @@
+//! Inter-chain contact, interface residue and clash analysis.
+//!
+//! Heavy atoms (no hydrogens or waters, first alternate location) are
+//! binned into a uniform grid with cells as wide as the largest cutoff, so
+//! each atom is only compared against the 27 surrounding cells.
+
+use crate::pdb::{parse_atoms, Atom};
+use crate::scorefile::ScoreTable;
+use crate::{QuiverCore, QuiverError, Result};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+
+/// Column names of [`InterfaceStats::to_pairs`], in order.
+pub const INTERFACE_TERMS: [&str; 5] =
+    ["contacts", "atom_contacts", "clashes", "n_interface_residues", "interface_residues"];
+
+/// Default heavy-atom distance (Å) for a contact.
+pub const DEFAULT_CONTACT_CUTOFF: f64 = 5.0;
+/// Default heavy-atom distance (Å) below which two atoms clash.
+pub const DEFAULT_CLASH_CUTOFF: f64 = 2.5;
+
+/// Records parsed and measured per parallel batch, bounding memory use.
+const CHUNK_SIZE: usize = 256;
+
+/// Points binned by integer cell coordinates.
+struct Grid {
+    cell: f64,
+    cells: HashMap<(i64, i64, i64), Vec<usize>>,
+}
+
+impl Grid {
+    fn new(points: &[[f64; 3]], cell: f64) -> Self {
+        let mut grid = Grid { cell, cells: HashMap::new() };
+        for (i, p) in points.iter().enumerate() {
+            grid.cells.entry(grid.key(p)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn key(&self, p: &[f64; 3]) -> (i64, i64, i64) {
+        (
+            (p[0] / self.cell).floor() as i64,
+            (p[1] / self.cell).floor() as i64,
+            (p[2] / self.cell).floor() as i64,
+        )
+    }
+
+    /// Indices of the points in the cells around `p`; a superset of the
+    /// points within one cell width.
+    fn around(&self, p: &[f64; 3]) -> impl Iterator<Item = usize> + '_ {
+        let (x, y, z) = self.key(p);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (x + dx, y + dy, z + dz))))
+            .filter_map(|key| self.cells.get(&key))
+            .flatten()
+            .copied()
+    }
+}
+
+/// Which chains are compared: each side is a set of chain IDs. Without a
+/// split, every pair of different chains is.
+#[derive(Debug, Clone, Default)]
+pub struct ChainSplit {
+    pub left: Vec<char>,
+    pub right: Vec<char>,
+}
+
+impl ChainSplit {
+    /// Parse `"A:B"` or `"AB:C"` (chains on each side of the interface).
+    pub fn parse(spec: &str) -> Result<Self> {
+        match spec.split_once(':') {
+            Some((left, right)) if !left.is_empty() && !right.is_empty() => {
+                let (left, right): (Vec<char>, Vec<char>) = (left.chars().collect(), right.chars().collect());
+                if left.iter().any(|c| right.contains(c)) {
+                    return Err(QuiverError::Invalid(format!("Chain groups in '{}' overlap", spec)));
+                }
+                Ok(ChainSplit { left, right })
+            }
+            _ => Err(QuiverError::Invalid(format!("Chain split must look like 'A:B' or 'AB:C', not '{}'", spec))),
+        }
+    }
+
+    fn across(&self, a: char, b: char) -> bool {
+        (self.left.contains(&a) && self.right.contains(&b)) || (self.left.contains(&b) && self.right.contains(&a))
+    }
+}
+
+/// Interface metrics of one record.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceStats {
+    /// Pairs of residues on different sides with any atoms in contact.
+    pub contacts: usize,
+    /// Pairs of atoms on different sides within the contact cutoff.
+    pub atom_contacts: usize,
+    /// Pairs of atoms on different sides closer than the clash cutoff.
+    pub clashes: usize,
+    /// Interface residue labels (number and insertion code) per chain, in
+    /// chain order of first appearance.
+    pub residues: Vec<(char, Vec<String>)>,
+}
+
+impl InterfaceStats {
+    pub fn compute(atoms: &[Atom], split: Option<&ChainSplit>, contact_cutoff: f64, clash_cutoff: f64) -> Self {
+        let atoms: Vec<&Atom> = atoms
+            .iter()
+            .filter(|a| matches!(a.alt_loc, ' ' | 'A'))
+            .filter(|a| !matches!(a.element.to_ascii_uppercase().as_str(), "H" | "D"))
+            .filter(|a| !a.is_water())
+            .collect();
+        let across = |a: &Atom, b: &Atom| match split {
+            Some(split) => split.across(a.chain, b.chain),
+            None => a.chain != b.chain,
+        };
+        let points: Vec<[f64; 3]> = atoms.iter().map(|a| [a.x, a.y, a.z]).collect();
+        let grid = Grid::new(&points, contact_cutoff.max(clash_cutoff).max(0.1));
+
+        let mut stats = InterfaceStats::default();
+        let mut residue_pairs = BTreeSet::new();
+        let mut interface: Vec<(char, BTreeSet<(i64, char)>)> = Vec::new();
+        for (i, (a, p)) in atoms.iter().zip(&points).enumerate() {
+            for j in grid.around(p).filter(|&j| j > i) {
+                let b = atoms[j];
+                if !across(a, b) {
+                    continue;
+                }
+                let q = points[j];
+                let d2 = (p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2) + (p[2] - q[2]).powi(2);
+                if d2 < clash_cutoff * clash_cutoff {
+                    stats.clashes += 1;
+                }
+                if d2 > contact_cutoff * contact_cutoff {
+                    continue;
+                }
+                stats.atom_contacts += 1;
+                let (ra, rb) = ((a.chain, a.res_seq, a.i_code), (b.chain, b.res_seq, b.i_code));
+                residue_pairs.insert(if ra < rb { (ra, rb) } else { (rb, ra) });
+                for atom in [a, b] {
+                    let pos = match interface.iter().position(|(chain, _)| *chain == atom.chain) {
+                        Some(pos) => pos,
+                        None => {
+                            interface.push((atom.chain, BTreeSet::new()));
+                            interface.len() - 1
+                        }
+                    };
+                    interface[pos].1.insert((atom.res_seq, atom.i_code));
+                }
+            }
+        }
+        stats.contacts = residue_pairs.len();
+        // Report chains in file order rather than in order of first contact.
+        let order: Vec<char> = atoms.iter().map(|a| a.chain).fold(Vec::new(), |mut order, chain| {
+            if !order.contains(&chain) {
+                order.push(chain);
+            }
+            order
+        });
+        interface.sort_by_key(|(chain, _)| order.iter().position(|c| c == chain));
+        stats.residues = interface
+            .into_iter()
+            .map(|(chain, residues)| {
+                let labels = residues
+                    .into_iter()
+                    .map(|(seq, i_code)| if i_code == ' ' { seq.to_string() } else { format!("{}{}", seq, i_code) })
+                    .collect();
+                (chain, labels)
+            })
+            .collect();
+        stats
+    }
+
+    /// The metrics as score terms, named as in [`INTERFACE_TERMS`].
+    /// Interface residues are written as `A:10,11,12;B:5,6`.
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let residues = self
+            .residues
+            .iter()
+            .map(|(chain, labels)| format!("{}:{}", chain, labels.join(",")))
+            .collect::<Vec<_>>()
+            .join(";");
+        let values = [
+            self.contacts.to_string(),
+            self.atom_contacts.to_string(),
+            self.clashes.to_string(),
+            self.residues.iter().map(|(_, labels)| labels.len()).sum::<usize>().to_string(),
+            residues,
+        ];
+        INTERFACE_TERMS.iter().map(|term| term.to_string()).zip(values).collect()
+    }
+}
+
+impl QuiverCore {
+    /// Interface metrics of every record, in file order. Records are read
+    /// in fixed-size chunks and each chunk is measured in parallel.
+    pub fn interfaces(
+        &self,
+        split: Option<&ChainSplit>,
+        contact_cutoff: f64,
+        clash_cutoff: f64,
+    ) -> Result<Vec<(String, InterfaceStats)>> {
+        let mut stats = Vec::with_capacity(self.tags.len());
+        let mut records = self.records()?;
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        loop {
+            for record in records.by_ref().take(CHUNK_SIZE) {
+                let record = record?;
+                chunk.push((record.tag, record.lines));
+            }
+            if chunk.is_empty() {
+                return Ok(stats);
+            }
+            stats.par_extend(chunk.par_drain(..).map(|(tag, lines)| {
+                (tag, InterfaceStats::compute(&parse_atoms(&lines), split, contact_cutoff, clash_cutoff))
+            }));
+        }
+    }
+}
+
+/// A table with one row per record and one column per metric.
+pub fn interface_table(stats: &[(String, InterfaceStats)]) -> ScoreTable {
+    let mut columns = vec!["tag".to_string()];
+    columns.extend(INTERFACE_TERMS.iter().map(|term| term.to_string()));
+    let rows = stats
+        .iter()
+        .map(|(tag, stats)| {
+            std::iter::once(Some(tag.clone()))
+                .chain(stats.to_pairs().into_iter().map(|(_, value)| Some(value)))
+                .collect()
+        })
+        .collect();
+    ScoreTable { columns, rows }
+}
@@ -1,47 +1,242 @@
-use pyo3::prelude::*;
-use pyo3::wrap_pyfunction;
+//! Reading and writing Quiver (`.qv`) archives: many PDB structures in one
+//! text file, each under a `QV_TAG` line with optional `QV_SCORE`,
+//! `QV_META` and `QV_CHECKSUM` annotations.
+//!
+//! [`QuiverCore`] opens an archive for reading (`"r"`) or appending
+//! (`"w"`); [`QuiverWriter`] keeps a buffered writer open for adding many
+//! records. Scores are `key=value` pairs, see [`parse_score_str`] and
+//! [`encode_score`]. The Python bindings are built with the `pyo3` feature.
+//!
+//! ```
+//! use quiver_pdb::{record_payload, QuiverCore, QuiverWriter};
+//!
+//! # fn main() -> quiver_pdb::Result<()> {
+//! let path = std::env::temp_dir().join(format!("doc_{}.qv", std::process::id()));
+//! let path = path.to_str().unwrap();
+//! let pdb = b"ATOM      1  CA  ALA A   1       0.000   0.000   0.000  1.00  0.00           C\n";
+//!
+//! let mut writer = QuiverWriter::open(path, None, None, false, false)?;
+//! writer.add("design_1", &record_payload(pdb, true), Some("ddg=-12.5"), None)?;
+//! writer.close()?;
+//!
+//! let qv = QuiverCore::new(path.to_string(), "r".to_string())?;
+//! for record in qv.records()? {
+//!     let record = record?;
+//!     assert_eq!(record.tag, "design_1");
+//!     assert_eq!(record.score.as_deref(), Some("ddg=-12.5"));
+//! }
+//! # std::fs::remove_file(path).ok();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, BufReader, Write, Read};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::Path;
-use std::collections::HashSet;
-use std::collections::HashMap;
-use std::str::FromStr;
+
+mod checksum;
+mod cluster;
+mod dataset;
+mod diff;
+mod dssp;
+mod error;
+mod export;
+mod filter;
+mod header;
+mod interface;
+mod mapped;
+mod pdb;
+#[cfg(feature = "pyo3")]
+mod python;
+mod rename;
+mod repair;
+mod sample;
+mod score;
+mod scorefile;
+mod select;
+mod sort;
+mod stats;
+mod superpose;
+mod tags;
+mod transform;
+mod writer;
+
+pub use checksum::{ChecksumAlgo, VerifyResult, VerifyStatus};
+pub use cluster::{ClusterMethod, ClusterMetric, Clustering};
+pub use dataset::{StructureBatches, DEFAULT_BATCH_SIZE};
+pub use diff::{ArchiveDiff, ScoreDiff, StructureDiff};
+pub use dssp::{dssp_table, SecondaryStructure, DSSP_TERMS};
+pub use error::{QuiverError, Result};
+pub use filter::ScoreCondition;
+pub use header::{QuiverHeader, FORMAT_VERSION};
+pub use interface::{
+    interface_table, ChainSplit, InterfaceStats, DEFAULT_CLASH_CUTOFF, DEFAULT_CONTACT_CUTOFF, INTERFACE_TERMS,
+};
+pub use mapped::MappedArchive;
+pub use pdb::{parse_atoms, Atom};
+pub use rename::TagRename;
+pub use repair::{DroppedRecord, RepairReport};
+pub use sample::{SampleSize, Strata};
+pub use score::{encode_score, parse_score_str, score_value, SCORE_ENCODING_VERSION};
+pub use scorefile::{ScoreFormat, ScoreTable};
+pub use select::Selection;
+pub use sort::SortKey;
+pub use stats::{stats_table, StructStats, STAT_TERMS};
+pub use superpose::{Coords, Superposition};
+pub use tags::TagMatcher;
+pub use transform::{strip_selection, Transform};
+pub use writer::{record_payload, write_pdb_files, QuiverWriter};
+
+use writer::encode_record;
+
+/// Take the exclusive advisory lock shared by all writers of `filename`. It
+/// is held on a `<filename>.lock` file next to the archive, so a rewrite can
+/// rename a new file over the archive without writers losing the lock.
+pub(crate) fn lock_archive(filename: &str) -> Result<File> {
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(format!("{}.lock", filename))?;
+    file.lock()?;
+    Ok(file)
+}
 
 #[derive(Debug)]
 pub struct QuiverCore {
     fnm: String,
     mode: String,
     tags: Vec<String>,
+    header: Option<QuiverHeader>,
+    header_pending: bool,
+    /// Take the archive's exclusive advisory lock around each append.
+    lock: bool,
+    /// File length when `tags` was last brought up to date.
+    known_len: u64,
+    /// Memory map of the file in read mode.
+    mapped: Option<MappedArchive>,
 }
 
 impl QuiverCore {
-    pub fn new(filename: String, mode: String) -> Result<Self, String> {
+    pub fn new(filename: String, mode: String) -> Result<Self> {
         if mode != "r" && mode != "w" {
-            return Err(format!(
+            return Err(QuiverError::Mode(format!(
                 "Quiver file must be opened in 'r' or 'w' mode, not '{}'", mode
-            ));
+            )));
+        }
+        let mapped = if mode == "r" && Path::new(&filename).exists() {
+            Some(MappedArchive::open(&filename)?)
+        } else {
+            None
+        };
+        // The length comes from the same scan as the tags, so a record
+        // appended meanwhile is picked up by the next `refresh_tags`.
+        let (tags, known_len) = match &mapped {
+            Some(mapped) => (mapped.tags(), mapped.mmap().len() as u64),
+            None => Self::read_tags(&filename)?,
+        };
+        let header = QuiverHeader::read(&filename)?;
+        Ok(QuiverCore { fnm: filename, mode, tags, header, header_pending: false, lock: false, known_len, mapped })
+    }
+
+    /// Give a new, empty file a `QV_HEADER` block, written just before the
+    /// first record. Files that already have content are left as they are.
+    pub fn enable_header(&mut self) {
+        if self.mode == "w" && self.known_len == 0 && !self.header_pending {
+            self.header_pending = true;
+            self.header = Some(QuiverHeader::for_new_file(&[]));
+        }
+    }
+
+    /// Enable advisory locking (`flock` on `<file>.lock`) around appends, so
+    /// several processes can safely add records to the same file.
+    pub fn set_locking(&mut self, lock: bool) {
+        self.lock = lock;
+    }
+
+    /// Pick up records appended by other writers since the tags were last
+    /// read, by scanning only the new tail of the file.
+    fn refresh_tags(&mut self, len: u64) -> Result<()> {
+        if len == self.known_len {
+            return Ok(());
+        }
+        let mut file = File::open(&self.fnm)?;
+        // Appended records start with `QV_TAG`; anything else at the old end
+        // means the file was rewritten in the meantime.
+        let mut start = [0u8; 6];
+        file.seek(SeekFrom::Start(self.known_len))?;
+        let appended = len > self.known_len && file.read_exact(&mut start).is_ok() && &start == b"QV_TAG";
+        if appended {
+            file.seek(SeekFrom::Start(self.known_len))?;
+            for line in BufReader::new(file.take(len - self.known_len)).lines() {
+                let line = line?;
+                if line.starts_with("QV_TAG") {
+                    if let Some(tag) = line.split_whitespace().nth(1) {
+                        self.tags.push(tag.to_string());
+                    }
+                }
+            }
+        } else {
+            self.tags = Self::read_tags(&self.fnm)?.0;
+        }
+        if self.header_pending && len > 0 {
+            // Another writer created the file first and wrote its header.
+            self.header_pending = false;
+            self.header = QuiverHeader::read(&self.fnm)?;
+        }
+        self.known_len = len;
+        Ok(())
+    }
+
+    /// The file's `QV_HEADER` block, if it has one.
+    pub fn header(&self) -> Option<&QuiverHeader> {
+        self.header.as_ref()
+    }
+
+    /// Add or replace a free-form header entry of a file being created.
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<()> {
+        if !self.header_pending {
+            return Err(QuiverError::Mode(format!(
+                "Header metadata can only be set on a new file with a header, before the first record is written to {}",
+                self.fnm
+            )));
         }
-        let tags = Self::read_tags(&filename)?;
-        Ok(QuiverCore { fnm: filename, mode, tags })
+        if let Some(header) = self.header.as_mut() {
+            header.set(key, value);
+        }
+        Ok(())
     }
 
-    fn read_tags(filename: &str) -> Result<Vec<String>, String> {
+    /// Write the header block, if any, so derived files keep it.
+    pub fn write_header<W: Write>(&self, out: &mut W) -> Result<()> {
+        match &self.header {
+            Some(header) => header.write(out),
+            None => Ok(()),
+        }
+    }
+
+    /// Tags of the file and the number of bytes scanned to find them.
+    fn read_tags(filename: &str) -> Result<(Vec<String>, u64)> {
         if !Path::new(filename).exists() {
-            return Ok(vec![]);
+            return Ok((vec![], 0));
         }
-        let file = File::open(filename).map_err(|e| e.to_string())?;
-        let reader = BufReader::new(file);
+        let file = File::open(filename)?;
+        let mut reader = BufReader::new(file);
         let mut tags = Vec::new();
-        for line in reader.lines() {
-            let line = line.map_err(|e| e.to_string())?;
-            if line.starts_with("QV_TAG") {
-                let parts: Vec<_> = line.split_whitespace().collect();
-                if parts.len() > 1 {
-                    tags.push(parts[1].to_string());
+        let mut len = 0u64;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line)?;
+            if n == 0 {
+                break;
+            }
+            len += n as u64;
+            if line.starts_with(b"QV_TAG") {
+                if let Some(tag) = String::from_utf8_lossy(&line).split_whitespace().nth(1) {
+                    tags.push(tag.to_string());
                 }
             }
         }
-        Ok(tags)
+        Ok((tags, len))
     }
 
     pub fn get_tags(&self) -> Vec<String> {
@@ -52,74 +247,83 @@ impl QuiverCore {
         self.tags.len()
     }
 
-    pub fn add_pdb(&mut self, pdb_lines: &[String], tag: &str, score_str: Option<&str>) -> Result<(), String> {
+    /// Append a record; `payload` should come from `record_payload`.
+    pub fn add_pdb(
+        &mut self,
+        payload: &[u8],
+        tag: &str,
+        score_str: Option<&str>,
+        meta_str: Option<&str>,
+        checksum: Option<ChecksumAlgo>,
+    ) -> Result<()> {
         if self.mode != "w" {
-            return Err("Quiver file must be opened in write mode to allow for writing.".to_string());
+            return Err(QuiverError::write_mode());
         }
+        // The lock is released when `_lock` is dropped at the end of this call.
+        let _lock = if self.lock { Some(lock_archive(&self.fnm)?) } else { None };
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.fnm)?;
+        let len = file.metadata()?.len();
+        self.refresh_tags(len)?;
         if self.tags.contains(&tag.to_string()) {
-            return Err(format!("Tag {} already exists in this file.", tag));
+            return Err(QuiverError::DuplicateTag(tag.to_string()));
         }
 
-        let mut file = OpenOptions::new().create(true).append(true).open(&self.fnm)
-            .map_err(|e| e.to_string())?;
-        writeln!(file, "QV_TAG {}", tag).map_err(|e| e.to_string())?;
-        if let Some(score) = score_str {
-            writeln!(file, "QV_SCORE {} {}", tag, score).map_err(|e| e.to_string())?;
-        }
-        for line in pdb_lines {
-            file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
-            if !line.ends_with('\n') {
-                file.write_all(b"\n").map_err(|e| e.to_string())?;
-            }
+        // Build the whole record first so it reaches the file in one write.
+        let mut record = Vec::new();
+        if self.header_pending {
+            self.write_header(&mut record)?;
         }
+        encode_record(&mut record, tag, payload, score_str, meta_str, checksum);
+        file.write_all(&record)?;
+
+        self.header_pending = false;
+        self.known_len = len + record.len() as u64;
         self.tags.push(tag.to_string());
         Ok(())
     }
 
-    pub fn get_pdblines(&self, tag: &str) -> Result<Vec<String>, String> {
+    pub fn mapped(&self) -> Option<&MappedArchive> {
+        self.mapped.as_ref()
+    }
+
+    /// The PDB payload of a record as raw bytes, borrowed from the memory
+    /// map unless annotation lines are interleaved with the payload.
+    pub fn get_pdb_bytes(&self, tag: &str) -> Result<Cow<'_, [u8]>> {
         if self.mode != "r" {
-            return Err("Quiver file must be opened in read mode to allow for reading.".to_string());
+            return Err(QuiverError::read_mode());
         }
-        let file = File::open(&self.fnm).map_err(|e| e.to_string())?;
-        let reader = BufReader::new(file);
-        let mut found = false;
-        let mut pdb_lines = Vec::new();
+        self.mapped
+            .as_ref()
+            .and_then(|mapped| mapped.payload(tag))
+            .ok_or_else(|| QuiverError::TagNotFound(tag.to_string()))
+    }
 
-        for line in reader.lines() {
-            let line = line.map_err(|e| e.to_string())?;
-            if line.starts_with("QV_TAG") {
-                let parts: Vec<_> = line.split_whitespace().collect();
-                if parts.len() > 1 && parts[1] == tag {
-                    found = true;
-                    continue;
-                } else if found {
-                    break;
-                }
-            }
-            if found && !line.starts_with("QV_SCORE") {
-                pdb_lines.push(line + "\n");
-            }
-        }
-        if !found {
-            return Err(format!("Requested tag: {} does not exist", tag));
-        }
-        Ok(pdb_lines)
+    pub fn get_pdblines(&self, tag: &str) -> Result<Vec<String>> {
+        let payload = self.get_pdb_bytes(tag)?;
+        payload
+            .split_inclusive(|&b| b == b'\n')
+            .map(|line| {
+                let line = line.strip_suffix(b"\n").unwrap_or(line);
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                String::from_utf8(line.to_vec()).map(|line| line + "\n").map_err(|e| QuiverError::Invalid(e.to_string()))
+            })
+            .collect()
     }
 
-    pub fn get_struct_list(&self, tag_list: &[String]) -> Result<(String, Vec<String>), String> {
+    pub fn get_struct_list(&self, tag_list: &[String]) -> Result<(String, Vec<String>)> {
         if self.mode != "r" {
-            return Err("Quiver file must be opened in read mode to allow for reading.".to_string());
+            return Err(QuiverError::read_mode());
         }
         let tag_set: HashSet<_> = tag_list.iter().cloned().collect();
         let mut found_tags = Vec::new();
         let mut struct_lines = String::new();
         let mut write_mode = false;
 
-        let file = File::open(&self.fnm).map_err(|e| e.to_string())?;
+        let file = File::open(&self.fnm)?;
         let reader = BufReader::new(file);
 
         for line in reader.lines() {
-            let line = line.map_err(|e| e.to_string())?;
+            let line = line?;
             if line.starts_with("QV_TAG") {
                 let parts: Vec<_> = line.split_whitespace().collect();
                 let current_tag = if parts.len() > 1 { parts[1] } else { "" };
@@ -136,433 +340,274 @@ impl QuiverCore {
         Ok((struct_lines, found_tags))
     }
 
-    pub fn split(&self, ntags: usize, outdir: &str, prefix: &str) -> Result<(), String> {
+    /// Iterate over the records of the file in order, one tag at a time.
+    pub fn records(&self) -> Result<QuiverRecords> {
         if self.mode != "r" {
-            return Err("Quiver file must be opened in read mode to allow for reading.".to_string());
+            return Err(QuiverError::read_mode());
         }
-        std::fs::create_dir_all(outdir).map_err(|e| e.to_string())?;
-
-        let mut file_idx = 0;
-        let mut tag_count = 0;
-        let mut out_file: Option<File> = None;
+        let file = File::open(&self.fnm)?;
+        Ok(QuiverRecords {
+            lines: BufReader::new(file).lines(),
+            pending: None,
+        })
+    }
 
-        let file = File::open(&self.fnm).map_err(|e| e.to_string())?;
-        let reader = BufReader::new(file);
+    /// Look up a single record by tag.
+    pub fn get_record(&self, tag: &str) -> Result<QuiverRecord> {
+        if let Some(mapped) = &self.mapped {
+            let bytes = mapped
+                .record_bytes(tag)
+                .ok_or_else(|| QuiverError::TagNotFound(tag.to_string()))?;
+            let mut records = QuiverRecords { lines: bytes.lines(), pending: None };
+            return records.next().unwrap_or_else(|| Err(QuiverError::TagNotFound(tag.to_string())));
+        }
+        for record in self.records()? {
+            let record = record?;
+            if record.tag == tag {
+                return Ok(record);
+            }
+        }
+        Err(QuiverError::TagNotFound(tag.to_string()))
+    }
 
-        for line in reader.lines() {
-            let line = line.map_err(|e| e.to_string())?;
-            if line.starts_with("QV_TAG") {
-                if tag_count % ntags == 0 {
-                    if let Some(mut f) = out_file.take() {
-                        f.flush().map_err(|e| e.to_string())?;
-                    }
-                    let out_path = Path::new(outdir).join(format!("{}_{}.qv", prefix, file_idx));
-                    out_file = Some(File::create(out_path).map_err(|e| e.to_string())?);
-                    file_idx += 1;
-                }
-                tag_count += 1;
+    /// Byte ranges of every record in file order, found in a single scan.
+    pub fn record_offsets(&self) -> Result<Vec<RecordOffset>> {
+        if let Some(mapped) = &self.mapped {
+            return Ok(mapped.records().to_vec());
+        }
+        let file = File::open(&self.fnm)?;
+        let mut reader = BufReader::new(file);
+        let mut offsets: Vec<RecordOffset> = Vec::new();
+        let mut pos = 0u64;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            if n == 0 {
+                break;
             }
-            if let Some(f) = out_file.as_mut() {
-                writeln!(f, "{}", line).map_err(|e| e.to_string())?;
+            if buf.starts_with(b"QV_TAG") {
+                if let Some(last) = offsets.last_mut() {
+                    last.end = pos;
+                }
+                let line = String::from_utf8_lossy(&buf);
+                let tag = line.split_whitespace().nth(1).unwrap_or("").to_string();
+                offsets.push(RecordOffset { tag, start: pos, end: pos });
             }
+            pos += n as u64;
         }
-        if let Some(mut f) = out_file {
-            f.flush().map_err(|e| e.to_string())?;
+        if let Some(last) = offsets.last_mut() {
+            last.end = pos;
         }
-        Ok(())
+        Ok(offsets)
     }
-}
 
-#[pyclass]
-struct Quiver {
-    core: QuiverCore,
-}
-
-#[pymethods]
-impl Quiver {
-    #[new]
-    fn new(filename: String, mode: String) -> PyResult<Self> {
-        match QuiverCore::new(filename, mode) {
-            Ok(core) => Ok(Quiver { core }),
-            Err(e) => Err(pyo3::exceptions::PyValueError::new_err(e)),
+    /// Copy the raw text of the given records to `out`, in the order given.
+    pub fn copy_records<W: Write>(&self, offsets: &[RecordOffset], out: &mut W) -> Result<()> {
+        if let Some(mapped) = &self.mapped {
+            for offset in offsets {
+                let bytes = &mapped.mmap()[offset.start as usize..offset.end as usize];
+                out.write_all(bytes)?;
+                if !bytes.ends_with(b"\n") {
+                    out.write_all(b"\n")?;
+                }
+            }
+            return Ok(());
         }
-    }
-
-    fn get_tags(&self) -> Vec<String> {
-        self.core.get_tags()
-    }
-
-    fn size(&self) -> usize {
-        self.core.size()
-    }
-
-    fn add_pdb(&mut self, pdb_lines: Vec<String>, tag: String, score_str: Option<String>) -> PyResult<()> {
-        match self.core.add_pdb(&pdb_lines, &tag, score_str.as_deref()) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+        let mut file = File::open(&self.fnm)?;
+        let mut buf = Vec::new();
+        for offset in offsets {
+            buf.resize((offset.end - offset.start) as usize, 0);
+            file.seek(SeekFrom::Start(offset.start))?;
+            file.read_exact(&mut buf)?;
+            out.write_all(&buf)?;
+            // The last record of a file may lack its trailing newline.
+            if !buf.ends_with(b"\n") {
+                out.write_all(b"\n")?;
+            }
         }
+        Ok(())
     }
 
-    fn get_pdblines(&self, tag: String) -> PyResult<Vec<String>> {
-        match self.core.get_pdblines(&tag) {
-            Ok(lines) => Ok(lines),
-            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+    /// Write the records whose tags are selected by `matcher` to `out`.
+    /// With `ordered`, records follow the order of the matcher's patterns
+    /// instead of file order. Returns the tags written.
+    pub fn write_slice<W: Write>(&self, matcher: &TagMatcher, ordered: bool, out: &mut W) -> Result<Vec<String>> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
         }
-    }
-
-    fn get_struct_list(&self, tag_list: Vec<String>) -> PyResult<(String, Vec<String>)> {
-        match self.core.get_struct_list(&tag_list) {
-            Ok(result) => Ok(result),
-            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+        let mut selected: Vec<(usize, RecordOffset)> = self
+            .record_offsets()?
+            .into_iter()
+            .filter_map(|offset| matcher.rank(&offset.tag).map(|rank| (rank, offset)))
+            .collect();
+        if ordered {
+            // Stable sort keeps file order among tags matching the same pattern.
+            selected.sort_by_key(|(rank, _)| *rank);
         }
+        let offsets: Vec<RecordOffset> = selected.into_iter().map(|(_, offset)| offset).collect();
+        self.write_header(out)?;
+        self.copy_records(&offsets, out)?;
+        Ok(offsets.into_iter().map(|offset| offset.tag).collect())
     }
 
-    fn split(&self, ntags: usize, outdir: String, prefix: String) -> PyResult<()> {
-        match self.core.split(ntags, &outdir, &prefix) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+    pub fn split(&self, ntags: usize, outdir: &str, prefix: &str) -> Result<()> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
         }
-    }
-}
-
-/// 여러 PDB 파일을 받아 Quiver 포맷으로 반환
-#[pyfunction]
-fn qvfrompdbs(pdb_files: Vec<String>) -> PyResult<String> {
-    let mut output = Vec::new();
-
-    for pdbfn in &pdb_files {
-        let path = Path::new(pdbfn);
-        let tag = path.file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("UNKNOWN");
-
-        writeln!(output, "QV_TAG {}", tag)
-            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        std::fs::create_dir_all(outdir)?;
 
-        let mut file = File::open(pdbfn)
-            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-        io::copy(&mut file, &mut output)
-            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-    }
-
-    Ok(String::from_utf8_lossy(&output).to_string())
-}
+        let mut file_idx = 0;
+        let mut tag_count = 0;
+        let mut out_file: Option<File> = None;
 
-#[pyfunction]
-fn extract_pdbs(py: Python, quiver_file: String) -> PyResult<()> {
-    // Quiver 인스턴스 생성
-    match Quiver::new(quiver_file.clone(), "r".to_string()) {
-        Ok(qv) => {
-            let tags = qv.get_tags();
-
-            for tag in tags {
-                let outfn = format!("{}.pdb", tag);
-
-                if Path::new(&outfn).exists() {
-                    // Python의 print를 사용해 경고 메시지 출력
-                    let builtins = py.import("builtins")?;
-                    builtins.getattr("print")?.call1((format!("⚠️  File {} already exists, skipping", outfn),))?;
-                    continue;
-                }
+        let file = File::open(&self.fnm)?;
+        let reader = BufReader::new(file);
 
-                // get_pdblines(tag)
-                match qv.get_pdblines(tag.clone()) {
-                    Ok(lines) => {
-                        // 파일로 저장
-                        let mut f = File::create(&outfn)
-                            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-                        for line in lines {
-                            f.write_all(line.as_bytes())
-                                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-                        }
-
-                        // 성공 메시지
-                        let builtins = py.import("builtins")?;
-                        builtins.getattr("print")?.call1((format!("✅ Extracted {}", outfn),))?;
-                    }
-                    Err(e) => {
-                        let builtins = py.import("builtins")?;
-                        builtins.getattr("print")?.call1((format!("❌ Error extracting tag {}: {}", tag, e),))?;
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with("QV_TAG") {
+                if tag_count % ntags == 0 {
+                    if let Some(mut f) = out_file.take() {
+                        f.flush()?;
                     }
+                    let out_path = Path::new(outdir).join(format!("{}_{}.qv", prefix, file_idx));
+                    let mut f = File::create(out_path)?;
+                    self.write_header(&mut f)?;
+                    out_file = Some(f);
+                    file_idx += 1;
                 }
+                tag_count += 1;
             }
-
-            // 최종 메시지
-            let size = qv.size();
-            let builtins = py.import("builtins")?;
-            builtins.getattr("print")?.call1((
-                format!("\n🎉 Successfully processed {} tags from {}", size, quiver_file),
-            ))?;
-
-            Ok(())
-        }
-        Err(e) => Err(e),
-    }
-}
-
- // list_tags 함수 추가
-#[pyfunction]
-fn list_tags(py: Python, quiver_file: String) -> PyResult<()> {
-    match Quiver::new(quiver_file.clone(), "r".to_string()) {
-        Ok(qv) => {
-            let tags = qv.get_tags();
-            let builtins = py.import("builtins")?;
-            for tag in tags {
-                builtins.getattr("print")?.call1((tag,))?;
+            if let Some(f) = out_file.as_mut() {
+                writeln!(f, "{}", line)?;
             }
-            Ok(())
         }
-        Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+        if let Some(mut f) = out_file {
+            f.flush()?;
+        }
+        Ok(())
     }
-}
 
-// rename_tags 함수 추가
-#[pyfunction]
-fn rename_tags(py: Python, quiver_file: String, new_tags: Vec<String>) -> PyResult<()> {
-    match Quiver::new(quiver_file.clone(), "r".to_string()) {
-        Ok(qv) => {
-            let present_tags = qv.get_tags();
-
-            if present_tags.len() != new_tags.len() {
-                let builtins = py.import("builtins")?;
-                builtins.getattr("print")?.call1((
-                    format!("❌ Number of tags in file ({}) does not match number of tags provided ({})", present_tags.len(), new_tags.len()),
-                ))?;
-                return Ok(()); // Python 스크립트의 sys.exit(1)과 유사하게 종료
+    /// Concatenate several Quiver files into `out`. The header of the first
+    /// input that has one is kept, extended with keys only later inputs
+    /// define. Tags must be unique across all inputs; this is checked before
+    /// anything is written. Returns the number of records written.
+    pub fn merge<W: Write>(inputs: &[String], out: &mut W) -> Result<usize> {
+        let mut cores = Vec::with_capacity(inputs.len());
+        let mut seen = HashSet::new();
+        let mut header: Option<QuiverHeader> = None;
+        for input in inputs {
+            let core = QuiverCore::new(input.clone(), "r".to_string())?;
+            for tag in &core.tags {
+                if !seen.insert(tag.clone()) {
+                    return Err(QuiverError::Invalid(format!(
+                        "Tag {} appears more than once across the inputs ({})", tag, input
+                    )));
+                }
             }
-
-            let mut tag_idx = 0;
-            let mut output_lines = Vec::new();
-            let file = File::open(&quiver_file).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-            let reader = BufReader::new(file);
-
-            let mut lines_iter = reader.lines();
-            while let Some(result_line) = lines_iter.next() {
-                let line = result_line.map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-                if line.starts_with("QV_TAG") {
-                    output_lines.push(format!("QV_TAG {}\n", new_tags[tag_idx]));
-
-                    if let Some(result_next_line) = lines_iter.next() {
-                        let next_line = result_next_line.map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-                        if next_line.starts_with("QV_TAG") {
-                            let builtins = py.import("builtins")?;
-                            builtins.getattr("print")?.call1((
-                                format!("❌ Error: Found two QV_TAG lines in a row. This is not supported. Line: {}", next_line),
-                            ))?;
-                            return Ok(());
-                        }
-                        if next_line.starts_with("QV_SCORE") {
-                            let parts: Vec<_> = next_line.split_whitespace().collect();
-                            if parts.len() > 2 {
-                                output_lines.push(format!("QV_SCORE {} {}\n", new_tags[tag_idx], parts[2]));
-                            } else {
-                                output_lines.push(next_line.to_string() + "\n");
-                            }
-                        } else {
-                            output_lines.push(next_line.to_string() + "\n");
-                        }
+            if let Some(input_header) = &core.header {
+                let merged = header.get_or_insert_with(QuiverHeader::default);
+                for (key, value) in &input_header.entries {
+                    if merged.get(key).is_none() {
+                        merged.set(key, value);
                     }
-                    tag_idx += 1;
-                } else {
-                    output_lines.push(line.to_string() + "\n");
                 }
             }
-
-            // 파일 덮어쓰기
-            let mut outfile = File::create(&quiver_file).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-            for line in output_lines {
-                outfile.write_all(line.as_bytes()).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-            }
-
-            let builtins = py.import("builtins")?;
-            builtins.getattr("print")?.call1((format!("✅ Successfully renamed tags in {}", quiver_file),))?;
-
-            Ok(())
+            cores.push(core);
         }
-        Err(e) => Err(e),
-    }
-}
 
-// qvslice 함수 추가
-#[pyfunction]
-fn qvslice(py: Python, quiver_file: String, tags: Option<Vec<String>>) -> PyResult<()> {
-    let mut tag_list = tags.unwrap_or_else(Vec::new);
-
-    // Read tags from stdin if no arguments are provided
-    if tag_list.is_empty() {
-        let stdin = io::stdin();
-        let mut stdin_reader = stdin.lock();
-        let mut stdin_data = Vec::new();
-        match stdin_reader.read_to_end(&mut stdin_data) {
-            Ok(_) => {
-                let stdin_str = String::from_utf8_lossy(&stdin_data);
-                tag_list.extend(stdin_str.trim().split_whitespace().map(String::from));
-            }
-            Err(e) => {
-                let builtins = py.import("builtins")?;
-                builtins.getattr("print")?.call1((
-                    format!("❌ Error reading from stdin: {}", e),
-                ))?;
-                return Ok(());
-            }
+        if let Some(header) = &header {
+            header.write(out)?;
+        }
+        let mut count = 0;
+        for core in &cores {
+            let offsets = core.record_offsets()?;
+            core.copy_records(&offsets, out)?;
+            count += offsets.len();
         }
+        Ok(count)
     }
+}
 
-    // Clean and validate tag list
-    tag_list.retain(|tag| !tag.trim().is_empty());
-    if tag_list.is_empty() {
-        let builtins = py.import("builtins")?;
-        builtins.getattr("print")?.call1((
-            "❌ No tags provided. Provide tags as arguments or via stdin.",
-        ))?;
-        return Ok(());
-    }
+/// Whether a line is a `QV_*` annotation (score, metadata, checksum or
+/// header) rather than part of a record's PDB payload.
+pub(crate) fn is_annotation(line: &[u8]) -> bool {
+    line.starts_with(b"QV_SCORE")
+        || line.starts_with(b"QV_META")
+        || line.starts_with(b"QV_CHECKSUM")
+        || line.starts_with(b"QV_HEADER")
+}
 
-    match Quiver::new(quiver_file.clone(), "r".to_string()) {
-        Ok(qv) => {
-            let tag_list_clone = tag_list.clone();
-            match qv.get_struct_list(tag_list_clone) {
-                Ok((qv_lines, found_tags)) => {
-                    let builtins = py.import("builtins")?;
-
-                    // Warn about missing tags
-                    let tag_set: HashSet<_> = found_tags.iter().collect();
-                    for tag in &tag_list {
-                        if !tag_set.contains(tag) {
-                            builtins.getattr("print")?.call1((format!("⚠️  Tag not found in Quiver file: {}", tag),))?;
-                        }
-                    }
+/// A single tagged structure: its tag, optional score and metadata strings
+/// and PDB lines.
+#[derive(Debug, Clone)]
+pub struct QuiverRecord {
+    pub tag: String,
+    pub score: Option<String>,
+    pub meta: Option<String>,
+    /// The encoded `QV_CHECKSUM` value, if the record has one.
+    pub checksum: Option<String>,
+    pub lines: Vec<String>,
+}
 
-                    // Output sliced content
-                    builtins.getattr("print")?.call1((qv_lines,))?;
-                    Ok(())
-                }
-                Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
-            }
-        }
-        Err(e) => Err(e),
-    }
+/// Location of a record inside a Quiver file, as a half-open byte range.
+#[derive(Debug, Clone)]
+pub struct RecordOffset {
+    pub tag: String,
+    pub start: u64,
+    pub end: u64,
 }
 
-// qvsplit 함수 추가
-#[pyfunction]
-fn qvsplit(py: Python, file: String, ntags: usize, prefix: String, output_dir: String) -> PyResult<()> {
-    if ntags == 0 {
-        let builtins = py.import("builtins")?;
-        builtins.getattr("print")?.call1(("❌ NTAGS must be a positive integer.",))?;
-        return Err(pyo3::exceptions::PyValueError::new_err("NTAGS must be a positive integer."));
-    }
+/// Streaming iterator over the records of a Quiver file.
+pub struct QuiverRecords<R = BufReader<File>> {
+    lines: io::Lines<R>,
+    pending: Option<String>,
+}
 
-    match Quiver::new(file.clone(), "r".to_string()) {
-        Ok(q) => {
-            match q.split(ntags, output_dir.clone(), prefix.clone()) {
-                Ok(_) => {
-                    let builtins = py.import("builtins")?;
-                    builtins.getattr("print")?.call1((
-                        format!("✅ Files written to {} with prefix '{}'", output_dir, prefix),
-                    ))?;
-                    Ok(())
+impl<R: BufRead> Iterator for QuiverRecords<R> {
+    type Item = Result<QuiverRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag_line = match self.pending.take() {
+            Some(line) => line,
+            None => loop {
+                match self.lines.next()? {
+                    Ok(line) if line.starts_with("QV_TAG") => break line,
+                    Ok(_) => continue,
+                    Err(e) => return Some(Err(e.into())),
                 }
-                Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+            },
+        };
+        let tag = tag_line.split_whitespace().nth(1).unwrap_or("").to_string();
+        let mut record = QuiverRecord { tag, score: None, meta: None, checksum: None, lines: Vec::new() };
+
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.starts_with("QV_TAG") {
+                self.pending = Some(line);
+                break;
             }
-        }
-        Err(e) => Err(e),
-    }
-}
-
-// extract_scorefile 함수 추가
-#[pyfunction]
-fn extract_scorefile(py: Python, quiver_file: String) -> PyResult<()> {
-    let mut records = Vec::new();
-    let file = File::open(&quiver_file).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-    let reader = BufReader::new(file);
-
-    for line in reader.lines() {
-        let line = line.map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-        if line.starts_with("QV_SCORE") {
-            let splits: Vec<_> = line.split_whitespace().collect();
-            if splits.len() < 3 {
+            if line.starts_with("QV_SCORE") {
+                record.score = line.split_whitespace().nth(2).map(String::from);
                 continue;
             }
-            let tag = splits[1].to_string();
-
-            let mut scores: HashMap<String, String> = HashMap::new();
-            scores.insert("tag".to_string(), tag.clone());
-
-            for entry in splits[2].split('|') {
-                let parts: Vec<_> = entry.split('=').collect();
-                if parts.len() == 2 {
-                    if let Ok(score) = f64::from_str(parts[1]) {
-                        scores.insert(parts[0].to_string(), score.to_string());
-                    } else {
-                        let builtins = py.import("builtins")?;
-                        builtins.getattr("print")?.call1((
-                            format!("❌ Failed parsing scores for tag {}: Invalid number format", tag),
-                        ))?;
-                        continue;
-                    }
-                }
+            if line.starts_with("QV_META") {
+                record.meta = line.split_whitespace().nth(2).map(String::from);
+                continue;
             }
-            records.push(scores);
-        }
-    }
-
-    if records.is_empty() {
-        let builtins = py.import("builtins")?;
-        builtins.getattr("print")?.call1(("❌ No score lines found in Quiver file.",))?;
-        return Err(pyo3::exceptions::PyValueError::new_err("No score lines found in Quiver file."));
-    }
-
-    // CSV 파일로 저장
-    let path = Path::new(&quiver_file).with_extension("sc");
-    let outfn = path.to_str()
-        .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("Invalid file path"))?;
-
-    let mut file = File::create(outfn).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-
-    // 헤더 작성
-    let mut headers = Vec::new();
-    headers.push("tag".to_string());
-    for record in &records {
-        for key in record.keys() {
-            if key != "tag" && !headers.contains(key) {
-                headers.push(key.clone());
+            if line.starts_with("QV_CHECKSUM") {
+                record.checksum = line.split_whitespace().nth(2).map(String::from);
+                continue;
             }
-        }
-    }
-    writeln!(file, "{}", headers.join("\t")).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
-
-    // 데이터 작성
-    for record in &records {
-        let mut row = Vec::new();
-        for header in &headers {
-            if let Some(value) = record.get(header) {
-                row.push(value.clone());
-            } else {
-                row.push("NaN".to_string());
+            if is_annotation(line.as_bytes()) {
+                continue;
             }
+            record.lines.push(line + "\n");
         }
-        writeln!(file, "{}", row.join("\t")).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        Some(Ok(record))
     }
-
-    let builtins = py.import("builtins")?;
-    builtins.getattr("print")?.call1((
-        format!("✅ Scorefile written to: {}", outfn),
-    ))?;
-
-    Ok(())
 }
-
-/// A Python module implemented in Rust.
-#[pymodule]
-fn quiver_pdb(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(qvfrompdbs, m)?)?;
-    m.add_function(wrap_pyfunction!(extract_pdbs, m)?)?;
-    m.add_function(wrap_pyfunction!(list_tags, m)?)?;
-    m.add_function(wrap_pyfunction!(rename_tags, m)?)?;
-    m.add_function(wrap_pyfunction!(qvslice, m)?)?;
-    m.add_function(wrap_pyfunction!(qvsplit, m)?)?;
-    m.add_function(wrap_pyfunction!(extract_scorefile, m)?)?;
-    m.add_class::<Quiver>()?;
-    Ok(())
-}
\ No newline at end of file
@@ -0,0 +1,111 @@
+//! Memory-mapped read access to a Quiver file.
+//!
+//! The file is mapped once and every record is located in a single pass over
+//! the mapped bytes, so lookups by tag and record slicing need neither a
+//! re-scan nor per-line allocation. Pages are shared between processes that
+//! map the same archive. A mapped file must not be truncated or rewritten in
+//! place while it is open; appends by other writers are not seen.
+
+use crate::{is_annotation, RecordOffset, Result};
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct MappedArchive {
+    map: Arc<Mmap>,
+    records: Vec<RecordOffset>,
+    /// First record index for each tag.
+    index: HashMap<String, usize>,
+}
+
+impl MappedArchive {
+    pub fn open(filename: &str) -> Result<Self> {
+        let file = File::open(filename)?;
+        // SAFETY: the mapping is read-only; callers must not truncate the file
+        // while it is open (see the module docs).
+        let map = unsafe { Mmap::map(&file) }?;
+
+        let mut records: Vec<RecordOffset> = Vec::new();
+        let mut pos = 0;
+        for line in map.split_inclusive(|&b| b == b'\n') {
+            if line.starts_with(b"QV_TAG") {
+                if let Some(last) = records.last_mut() {
+                    last.end = pos as u64;
+                }
+                let text = String::from_utf8_lossy(line);
+                let tag = text.split_whitespace().nth(1).unwrap_or("").to_string();
+                records.push(RecordOffset { tag, start: pos as u64, end: pos as u64 });
+            }
+            pos += line.len();
+        }
+        if let Some(last) = records.last_mut() {
+            last.end = pos as u64;
+        }
+        let mut index = HashMap::with_capacity(records.len());
+        for (i, record) in records.iter().enumerate() {
+            index.entry(record.tag.clone()).or_insert(i);
+        }
+        Ok(MappedArchive { map: Arc::new(map), records, index })
+    }
+
+    pub fn mmap(&self) -> &Arc<Mmap> {
+        &self.map
+    }
+
+    pub fn records(&self) -> &[RecordOffset] {
+        &self.records
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.records.iter().map(|r| r.tag.clone()).collect()
+    }
+
+    /// Raw bytes of the first record with `tag`, including its `QV_*` lines.
+    pub fn record_bytes(&self, tag: &str) -> Option<&[u8]> {
+        let record = &self.records[*self.index.get(tag)?];
+        Some(&self.map[record.start as usize..record.end as usize])
+    }
+
+    /// Byte range of the PDB payload of `tag` within the mapping, when the
+    /// payload is contiguous (annotations only directly after `QV_TAG`, as
+    /// every writer in this crate produces them).
+    pub fn payload_range(&self, tag: &str) -> Option<Range<usize>> {
+        let record = &self.records[*self.index.get(tag)?];
+        let (start, end) = (record.start as usize, record.end as usize);
+        let mut lines = self.map[start..end].split_inclusive(|&b| b == b'\n');
+        let mut payload_start = start + lines.next().map_or(0, <[u8]>::len);
+        let mut in_payload = false;
+        for line in lines {
+            if is_annotation(line) {
+                if in_payload {
+                    return None;
+                }
+                payload_start += line.len();
+            } else {
+                in_payload = true;
+            }
+        }
+        Some(payload_start..end)
+    }
+
+    /// PDB payload of `tag`: borrowed from the mapping when contiguous,
+    /// otherwise gathered with the annotation lines left out.
+    pub fn payload(&self, tag: &str) -> Option<Cow<'_, [u8]>> {
+        if let Some(range) = self.payload_range(tag) {
+            return Some(Cow::Borrowed(&self.map[range]));
+        }
+        let bytes = self.record_bytes(tag)?;
+        let gathered = bytes
+            .split_inclusive(|&b| b == b'\n')
+            .skip(1)
+            .filter(|line| !is_annotation(line))
+            .flatten()
+            .copied()
+            .collect();
+        Some(Cow::Owned(gathered))
+    }
+}
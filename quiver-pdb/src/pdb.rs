@@ -0,0 +1,115 @@
+//! Minimal fixed-column PDB atom record handling.
+
+/// One ATOM/HETATM record parsed from a PDB line.
+#[derive(Debug, Clone)]
+pub struct Atom {
+    pub hetatm: bool,
+    pub serial: i64,
+    pub name: String,
+    pub alt_loc: char,
+    pub res_name: String,
+    pub chain: char,
+    pub res_seq: i64,
+    pub i_code: char,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub occupancy: f64,
+    pub b_factor: f64,
+    pub element: String,
+}
+
+/// Residue names treated as water.
+pub(crate) const WATER: [&str; 6] = ["HOH", "WAT", "H2O", "DOD", "TIP3", "SOL"];
+
+fn column(line: &str, start: usize, end: usize) -> &str {
+    let end = end.min(line.len());
+    if start >= end {
+        return "";
+    }
+    line.get(start..end).unwrap_or("")
+}
+
+fn column_char(line: &str, idx: usize) -> char {
+    line.as_bytes().get(idx).map(|&b| b as char).unwrap_or(' ')
+}
+
+impl Atom {
+    /// Parse an ATOM or HETATM line. Any other record type yields `None`.
+    pub fn parse(line: &str) -> Option<Atom> {
+        let hetatm = if line.starts_with("ATOM") {
+            false
+        } else if line.starts_with("HETATM") {
+            true
+        } else {
+            return None;
+        };
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let name = column(line, 12, 16).trim().to_string();
+        let mut element = column(line, 76, 78).trim().to_string();
+        if element.is_empty() {
+            element = name
+                .chars()
+                .find(|c| c.is_ascii_alphabetic())
+                .map(|c| c.to_string())
+                .unwrap_or_default();
+        }
+
+        Some(Atom {
+            hetatm,
+            serial: column(line, 6, 11).trim().parse().unwrap_or(0),
+            name,
+            alt_loc: column_char(line, 16),
+            res_name: column(line, 17, 20).trim().to_string(),
+            chain: column_char(line, 21),
+            res_seq: column(line, 22, 26).trim().parse().ok()?,
+            i_code: column_char(line, 26),
+            x: column(line, 30, 38).trim().parse().ok()?,
+            y: column(line, 38, 46).trim().parse().ok()?,
+            z: column(line, 46, 54).trim().parse().ok()?,
+            occupancy: column(line, 54, 60).trim().parse().unwrap_or(1.0),
+            b_factor: column(line, 60, 66).trim().parse().unwrap_or(0.0),
+            element,
+        })
+    }
+
+    /// Whether the residue name is one of [`WATER`].
+    pub fn is_water(&self) -> bool {
+        WATER.contains(&self.res_name.to_ascii_uppercase().as_str())
+    }
+}
+
+/// Parse every ATOM/HETATM line of a record, skipping anything else.
+pub fn parse_atoms(lines: &[String]) -> Vec<Atom> {
+    lines.iter().filter_map(|line| Atom::parse(line)).collect()
+}
+
+/// One-letter code of an amino acid residue name; `X` for anything else.
+pub(crate) fn one_letter(res_name: &str) -> char {
+    match res_name.to_ascii_uppercase().as_str() {
+        "ALA" => 'A',
+        "ARG" => 'R',
+        "ASN" => 'N',
+        "ASP" => 'D',
+        "CYS" => 'C',
+        "GLN" => 'Q',
+        "GLU" => 'E',
+        "GLY" => 'G',
+        "HIS" | "HID" | "HIE" | "HIP" => 'H',
+        "ILE" => 'I',
+        "LEU" => 'L',
+        "LYS" => 'K',
+        "MET" | "MSE" => 'M',
+        "PHE" => 'F',
+        "PRO" => 'P',
+        "SER" => 'S',
+        "THR" => 'T',
+        "TRP" => 'W',
+        "TYR" => 'Y',
+        "VAL" => 'V',
+        "SEC" => 'U',
+        "PYL" => 'O',
+        _ => 'X',
+    }
+}
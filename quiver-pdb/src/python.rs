@@ -0,0 +1,1536 @@
+//! Python bindings, built with the `pyo3` feature.
+
+use crate::checksum::{self, ChecksumAlgo};
+use crate::cluster::{ClusterMethod, ClusterMetric};
+use crate::dataset::{self, StructureBatches};
+use crate::interface::{self, ChainSplit};
+use crate::rename::TagRename;
+use crate::sample::{SampleSize, Strata};
+use crate::score::{self, encode_score, parse_score_str};
+use crate::scorefile::{ScoreFormat, ScoreTable};
+use crate::select::Selection;
+use crate::sort::SortKey;
+use crate::superpose::Coords;
+use crate::tags::TagMatcher;
+use crate::transform::{self, Transform};
+use crate::writer::{lines_payload, record_payload, write_pdb_files, QuiverWriter};
+use crate::{dssp, pdb, stats, QuiverCore, QuiverError};
+use arrow_array::ffi::FFI_ArrowSchema;
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
+use arrow_array::RecordBatchReader;
+use arrow_schema::SchemaRef;
+use memmap2::Mmap;
+use numpy::{PyArray1, PyArrayMethods};
+use pyo3::prelude::*;
+use pyo3::types::{PyByteArray, PyBytes, PyCapsule, PyDict, PyList, PyMemoryView, PyString};
+use pyo3::wrap_pyfunction;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{c_int, c_void};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+#[pyclass]
+struct Quiver {
+    core: QuiverCore,
+}
+
+#[pymethods]
+impl Quiver {
+    /// With `header` (implied by `metadata`), a new file starts with a
+    /// `QV_HEADER` block.
+    #[new]
+    #[pyo3(signature = (filename, mode, metadata=None, lock=false, header=false))]
+    fn new(
+        filename: String,
+        mode: String,
+        metadata: Option<HashMap<String, String>>,
+        lock: bool,
+        header: bool,
+    ) -> PyResult<Self> {
+        let mut core = QuiverCore::new(filename, mode).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        core.set_locking(lock);
+        if header || metadata.is_some() {
+            core.enable_header();
+        }
+        if let Some(metadata) = metadata {
+            let mut entries: Vec<_> = metadata.into_iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                core.set_metadata(&key, &value).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+            }
+        }
+        Ok(Quiver { core })
+    }
+
+    /// File-level `QV_HEADER` entries; empty for files without a header.
+    #[getter(metadata)]
+    fn file_metadata(&self) -> HashMap<String, String> {
+        self.core.header().map(|h| h.entries.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        self.core.get_tags()
+    }
+
+    fn size(&self) -> usize {
+        self.core.size()
+    }
+
+    /// `pdb_lines` may be a list of lines, a str, bytes or a file path.
+    /// `score_str` and `metadata` may be pre-encoded strings or dicts, which
+    /// are serialized with escaping.
+    /// `checksum` ("crc32" or "xxh64") records a `QV_CHECKSUM` line.
+    /// `normalize` converts CRLF line endings and drops trailing END/blank lines.
+    #[pyo3(signature = (pdb_lines, tag, score_str=None, metadata=None, checksum=None, normalize=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn add_pdb(
+        &mut self,
+        pdb_lines: &Bound<'_, PyAny>,
+        tag: String,
+        score_str: Option<&Bound<'_, PyAny>>,
+        metadata: Option<&Bound<'_, PyAny>>,
+        checksum: Option<&str>,
+        normalize: bool,
+    ) -> PyResult<()> {
+        let text = pdb_text(pdb_lines)?;
+        let payload = record_payload(&text, normalize);
+        let score_str = score_str.map(encode_score_arg).transpose()?;
+        let meta_str = metadata.map(encode_score_arg).transpose()?;
+        let checksum = checksum
+            .map(ChecksumAlgo::from_name)
+            .transpose()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        match self.core.add_pdb(&payload, &tag, score_str.as_deref(), meta_str.as_deref(), checksum) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e.to_string())),
+        }
+    }
+
+    /// Decoded scores of a tag. Numeric values are returned as floats.
+    fn get_scores<'py>(&self, py: Python<'py>, tag: String) -> PyResult<Bound<'py, PyDict>> {
+        let record = self.core.get_record(&tag).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        let dict = PyDict::new(py);
+        for (key, value) in parse_score_str(record.score.as_deref().unwrap_or("")) {
+            match value.parse::<f64>() {
+                Ok(number) => dict.set_item(key, number)?,
+                Err(_) => dict.set_item(key, value)?,
+            }
+        }
+        Ok(dict)
+    }
+
+    /// Decoded `QV_META` entries of a tag, as strings.
+    fn get_metadata(&self, tag: String) -> PyResult<HashMap<String, String>> {
+        let record = self.core.get_record(&tag).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        Ok(parse_score_str(record.meta.as_deref().unwrap_or("")).into_iter().collect())
+    }
+
+    /// `format` selects the result: "lines" (list of str), "bytes" (the
+    /// payload as one bytes object) or "memoryview" (a read-only view into
+    /// the memory-mapped file, without copying).
+    #[pyo3(signature = (tag, format="lines"))]
+    fn get_pdblines<'py>(&self, py: Python<'py>, tag: String, format: &str) -> PyResult<Bound<'py, PyAny>> {
+        match format {
+            "lines" => {
+                let lines = self.core.get_pdblines(&tag).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+                Ok(lines.into_pyobject(py)?.into_any())
+            }
+            "bytes" | "memoryview" => {
+                let range = self.core.mapped().and_then(|mapped| mapped.payload_range(&tag));
+                if let (Some(range), Some(mapped), "memoryview") = (range, self.core.mapped(), format) {
+                    let buffer = Bound::new(py, RecordBuffer { map: mapped.mmap().clone(), range })?;
+                    return Ok(PyMemoryView::from(buffer.as_any())?.into_any());
+                }
+                let payload = self.core.get_pdb_bytes(&tag).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+                let bytes = PyBytes::new(py, &payload).into_any();
+                match format {
+                    "bytes" => Ok(bytes),
+                    _ => Ok(PyMemoryView::from(&bytes)?.into_any()),
+                }
+            }
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "format must be 'lines', 'bytes' or 'memoryview', not '{}'", format
+            ))),
+        }
+    }
+
+    /// RMSD between two records after optimal superposition. `atoms` is
+    /// "ca", "backbone", "heavy" or a selection such as "chain A and name CA";
+    /// atoms are paired by chain, residue number, insertion code and name.
+    #[pyo3(signature = (tag_a, tag_b, atoms="ca"))]
+    fn rmsd(&self, tag_a: String, tag_b: String, atoms: &str) -> PyResult<f64> {
+        let selection = Selection::for_atoms(atoms).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let superposition = self.core.superpose(&tag_a, &tag_b, &selection).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(superposition.rmsd)
+    }
+
+    /// Superpose `mobile` onto `target`. Returns a dict with `rmsd`,
+    /// `n_atoms`, a 3x3 `rotation` and a `translation` array such that
+    /// `rotation @ x + translation` maps mobile coordinates onto the target.
+    #[pyo3(signature = (mobile, target, atoms="ca"))]
+    fn superpose<'py>(&self, py: Python<'py>, mobile: String, target: String, atoms: &str) -> PyResult<Bound<'py, PyDict>> {
+        let selection = Selection::for_atoms(atoms).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let superposition = self.core.superpose(&mobile, &target, &selection).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let rotation: Vec<f64> = superposition.rotation.iter().flatten().copied().collect();
+        let dict = PyDict::new(py);
+        dict.set_item("rmsd", superposition.rmsd)?;
+        dict.set_item("n_atoms", superposition.n_atoms)?;
+        dict.set_item("rotation", PyArray1::from_vec(py, rotation).reshape([3, 3])?)?;
+        dict.set_item("translation", PyArray1::from_vec(py, superposition.translation.to_vec()))?;
+        Ok(dict)
+    }
+
+    fn get_struct_list(&self, tag_list: Vec<String>) -> PyResult<(String, Vec<String>)> {
+        match self.core.get_struct_list(&tag_list) {
+            Ok(result) => Ok(result),
+            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e.to_string())),
+        }
+    }
+
+    fn split(&self, ntags: usize, outdir: String, prefix: String) -> PyResult<()> {
+        match self.core.split(ntags, &outdir, &prefix) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e.to_string())),
+        }
+    }
+}
+
+/// Read-only buffer over a record payload inside a memory-mapped archive.
+/// Keeps the mapping alive for as long as any view of it exists.
+#[pyclass]
+struct RecordBuffer {
+    map: Arc<Mmap>,
+    range: Range<usize>,
+}
+
+#[pymethods]
+impl RecordBuffer {
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut pyo3::ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        let bytes = &slf.borrow().map[slf.borrow().range.clone()];
+        let (ptr, len) = (bytes.as_ptr() as *mut c_void, bytes.len() as pyo3::ffi::Py_ssize_t);
+        // SAFETY: `view` comes from the interpreter; the data stays valid while
+        // `slf`, which the view holds a reference to, keeps the map alive.
+        if unsafe { pyo3::ffi::PyBuffer_FillInfo(view, slf.as_ptr(), ptr, len, 1, flags) } == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.range.len()
+    }
+}
+
+/// Buffered writer for adding many records without reopening the file.
+/// Usable as a context manager; the file is flushed and closed on exit.
+#[pyclass(name = "QuiverWriter")]
+struct PyQuiverWriter {
+    inner: QuiverWriter,
+}
+
+impl PyQuiverWriter {
+    fn add_record(
+        &mut self,
+        tag: &str,
+        pdb: &Bound<'_, PyAny>,
+        scores: Option<&Bound<'_, PyAny>>,
+        normalize: bool,
+    ) -> PyResult<()> {
+        let score_str = scores.map(encode_score_arg).transpose()?;
+        let text = pdb_text(pdb)?;
+        self.inner
+            .add(tag, &record_payload(&text, normalize), score_str.as_deref(), None)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+}
+
+#[pymethods]
+impl PyQuiverWriter {
+    /// `fsync` forces the data to disk on close; `lock` holds an exclusive
+    /// advisory lock for the writer's lifetime. With `header` (implied by
+    /// `metadata`), a new file starts with a `QV_HEADER` block.
+    #[new]
+    #[pyo3(signature = (filename, metadata=None, checksum=None, fsync=false, lock=false, header=false))]
+    fn new(
+        filename: String,
+        metadata: Option<HashMap<String, String>>,
+        checksum: Option<&str>,
+        fsync: bool,
+        lock: bool,
+        header: bool,
+    ) -> PyResult<Self> {
+        let checksum = checksum
+            .map(ChecksumAlgo::from_name)
+            .transpose()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let header = header || metadata.is_some();
+        let mut entries: Vec<_> = metadata.unwrap_or_default().into_iter().collect();
+        entries.sort();
+        let inner = QuiverWriter::open(&filename, header.then_some(entries.as_slice()), checksum, fsync, lock)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        Ok(PyQuiverWriter { inner })
+    }
+
+    /// `pdb` may be a list of lines, a str, bytes or a file path; `scores`
+    /// may be a pre-encoded string or a dict.
+    #[pyo3(signature = (tag, pdb, scores=None, normalize=false))]
+    fn add(&mut self, tag: String, pdb: &Bound<'_, PyAny>, scores: Option<&Bound<'_, PyAny>>, normalize: bool) -> PyResult<()> {
+        self.add_record(&tag, pdb, scores, normalize)
+    }
+
+    /// Add `(tag, pdb)` or `(tag, pdb, scores)` items from any iterable.
+    /// Returns the number of records added.
+    #[pyo3(signature = (items, normalize=false))]
+    fn add_many(&mut self, items: &Bound<'_, PyAny>, normalize: bool) -> PyResult<usize> {
+        let mut count = 0;
+        for item in items.try_iter()? {
+            let item = item?;
+            let len = item.len()?;
+            if len != 2 && len != 3 {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "add_many items must be (tag, pdb) or (tag, pdb, scores)",
+                ));
+            }
+            let tag: String = item.get_item(0)?.extract()?;
+            let pdb = item.get_item(1)?;
+            let scores = if len == 3 { Some(item.get_item(2)?) } else { None };
+            self.add_record(&tag, &pdb, scores.as_ref().filter(|s| !s.is_none()), normalize)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> PyResult<()> {
+        self.inner.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    fn close(&mut self) -> PyResult<()> {
+        self.inner.close().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        self.close()?;
+        Ok(false)
+    }
+}
+
+/// PDB text given as a list of lines, str, bytes, bytearray or a path
+/// (`os.PathLike`, or a single-line str naming an existing file). bytes and
+/// str are borrowed without copying where possible.
+fn pdb_text<'a>(obj: &'a Bound<'_, PyAny>) -> PyResult<Cow<'a, [u8]>> {
+    let read = |path: &Path| std::fs::read(path).map(Cow::Owned).map_err(|e| {
+        pyo3::exceptions::PyIOError::new_err(format!("{}: {}", path.display(), e))
+    });
+    if let Ok(bytes) = obj.cast::<PyBytes>() {
+        return Ok(Cow::Borrowed(bytes.as_bytes()));
+    }
+    if let Ok(bytes) = obj.cast::<PyByteArray>() {
+        return Ok(Cow::Owned(bytes.to_vec()));
+    }
+    if let Ok(text) = obj.cast::<PyString>() {
+        let text = text.to_cow()?;
+        if !text.contains('\n') && Path::new(text.as_ref()).is_file() {
+            return read(Path::new(text.as_ref()));
+        }
+        return Ok(match text {
+            Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+            Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+        });
+    }
+    if obj.hasattr("__fspath__")? {
+        let path: std::path::PathBuf = obj.extract()?;
+        return read(&path);
+    }
+    match obj.extract::<Vec<String>>() {
+        Ok(lines) => Ok(Cow::Owned(lines_payload(&lines))),
+        Err(_) => Err(pyo3::exceptions::PyTypeError::new_err(
+            "PDB must be a list of lines, str, bytes or a file path",
+        )),
+    }
+}
+
+/// Turn a score/metadata argument into an encoded string. Strings are used
+/// as given; dicts are serialized with `encode_score`.
+fn encode_score_arg(arg: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(dict) = arg.cast::<PyDict>() {
+        let mut pairs = Vec::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            pairs.push((key.str()?.to_string(), value.str()?.to_string()));
+        }
+        return Ok(encode_score(&pairs));
+    }
+    arg.extract::<String>()
+}
+
+/// 여러 PDB 파일을 받아 Quiver 포맷으로 반환
+#[pyfunction]
+#[pyo3(signature = (pdb_files, checksum=None, header=false))]
+fn qvfrompdbs(pdb_files: Vec<String>, checksum: Option<&str>, header: bool) -> PyResult<String> {
+    let checksum = checksum
+        .map(ChecksumAlgo::from_name)
+        .transpose()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let mut output = Vec::new();
+    write_pdb_files(&pdb_files, checksum, header, &mut output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    Ok(String::from_utf8_lossy(&output).to_string())
+}
+
+#[pyfunction]
+#[pyo3(signature = (quiver_file, select=None))]
+fn extract_pdbs(py: Python, quiver_file: String, select: Option<String>) -> PyResult<()> {
+    let selection = parse_selection(select.as_deref())?;
+    // Quiver 인스턴스 생성
+    match Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false) {
+        Ok(qv) => {
+            let tags = qv.get_tags();
+
+            for tag in tags {
+                let outfn = format!("{}.pdb", tag);
+
+                if Path::new(&outfn).exists() {
+                    // Python의 print를 사용해 경고 메시지 출력
+                    let builtins = py.import("builtins")?;
+                    builtins.getattr("print")?.call1((format!("⚠️  File {} already exists, skipping", outfn),))?;
+                    continue;
+                }
+
+                // get_pdblines(tag)
+                match qv.core.get_pdblines(&tag) {
+                    Ok(lines) => {
+                        let lines = match &selection {
+                            Some(selection) => selection.filter_lines(&lines),
+                            None => lines,
+                        };
+                        // 파일로 저장
+                        let mut f = File::create(&outfn)
+                            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+                        for line in lines {
+                            f.write_all(line.as_bytes())
+                                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+                        }
+
+                        // 성공 메시지
+                        let builtins = py.import("builtins")?;
+                        builtins.getattr("print")?.call1((format!("✅ Extracted {}", outfn),))?;
+                    }
+                    Err(e) => {
+                        let builtins = py.import("builtins")?;
+                        builtins.getattr("print")?.call1((format!("❌ Error extracting tag {}: {}", tag, e),))?;
+                    }
+                }
+            }
+
+            // 최종 메시지
+            let size = qv.size();
+            let builtins = py.import("builtins")?;
+            builtins.getattr("print")?.call1((
+                format!("\n🎉 Successfully processed {} tags from {}", size, quiver_file),
+            ))?;
+
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse an optional `select=` argument of the export functions.
+fn parse_selection(select: Option<&str>) -> PyResult<Option<Selection>> {
+    select
+        .map(Selection::parse)
+        .transpose()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+}
+
+// extract_multimodel_pdb 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, select=None))]
+fn extract_multimodel_pdb(py: Python, quiver_file: String, output: String, select: Option<String>) -> PyResult<()> {
+    let selection = parse_selection(select.as_deref())?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let count = qv.core.write_multimodel_pdb(&output, selection.as_ref())
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Wrote {} models from {} to {}", count, quiver_file, output),
+    ))?;
+    Ok(())
+}
+
+// extract_mmcif 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output_dir, select=None))]
+fn extract_mmcif(py: Python, quiver_file: String, output_dir: String, select: Option<String>) -> PyResult<()> {
+    let selection = parse_selection(select.as_deref())?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let count = qv.core.write_mmcif(&output_dir, selection.as_ref())
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Wrote {} mmCIF files from {} to {}", count, quiver_file, output_dir),
+    ))?;
+    Ok(())
+}
+
+// extract_tar 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, select=None))]
+fn extract_tar(py: Python, quiver_file: String, output: String, select: Option<String>) -> PyResult<()> {
+    let selection = parse_selection(select.as_deref())?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let count = qv.core.write_tar(&output, selection.as_ref())
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Archived {} PDBs from {} into {}", count, quiver_file, output),
+    ))?;
+    Ok(())
+}
+
+/// Arrow record batches of a Quiver file. pyarrow, polars and other Arrow
+/// libraries read them without copying through the Arrow PyCapsule
+/// interface; the stream can be consumed once.
+#[pyclass(name = "ArrowStream")]
+struct ArrowStream {
+    schema: SchemaRef,
+    batches: Option<StructureBatches>,
+}
+
+#[pymethods]
+impl ArrowStream {
+    fn __arrow_c_schema__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyCapsule>> {
+        let schema = FFI_ArrowSchema::try_from(self.schema.as_ref())
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        PyCapsule::new_with_value(py, schema, c"arrow_schema")
+    }
+
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__<'py>(
+        &mut self,
+        py: Python<'py>,
+        requested_schema: Option<Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyCapsule>> {
+        // Schema casts are not supported, so the requested schema is ignored.
+        let _ = requested_schema;
+        let batches = self.batches.take().ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err("ArrowStream has already been consumed.")
+        })?;
+        let stream = FFI_ArrowArrayStream::new(Box::new(batches));
+        PyCapsule::new_with_value(py, stream, c"arrow_array_stream")
+    }
+}
+
+// to_arrow 함수 추가
+/// Turn a Quiver file, or the records in `tags`, into Arrow record batches.
+/// With `parquet`, they are written to that Parquet file instead.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, tags=None, select=None, batch_size=dataset::DEFAULT_BATCH_SIZE, parquet=None))]
+fn to_arrow(
+    py: Python,
+    quiver_file: String,
+    tags: Option<Vec<String>>,
+    select: Option<String>,
+    batch_size: usize,
+    parquet: Option<String>,
+) -> PyResult<Option<ArrowStream>> {
+    let selection = parse_selection(select.as_deref())?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let batches = qv.core.structure_batches(tags.as_deref(), selection.as_ref(), batch_size)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+
+    match parquet {
+        Some(path) => {
+            let rows = py.detach(|| batches.write_parquet(&path))
+                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+            let builtins = py.import("builtins")?;
+            builtins.getattr("print")?.call1((
+                format!("✅ Wrote {} records from {} to {}", rows, quiver_file, path),
+            ))?;
+            Ok(None)
+        }
+        None => Ok(Some(ArrowStream { schema: batches.schema(), batches: Some(batches) })),
+    }
+}
+
+ // list_tags 함수 추가
+#[pyfunction]
+fn list_tags(py: Python, quiver_file: String) -> PyResult<()> {
+    match Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false) {
+        Ok(qv) => {
+            let tags = qv.get_tags();
+            let builtins = py.import("builtins")?;
+            for tag in tags {
+                builtins.getattr("print")?.call1((tag,))?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+    }
+}
+
+// rename_tags 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, new_tags=None, mapping=None, mapping_file=None, pattern=None, replacement=None, prefix=None, suffix=None))]
+#[allow(clippy::too_many_arguments)]
+fn rename_tags(
+    py: Python,
+    quiver_file: String,
+    new_tags: Option<Vec<String>>,
+    mapping: Option<HashMap<String, String>>,
+    mapping_file: Option<String>,
+    pattern: Option<String>,
+    replacement: Option<String>,
+    prefix: Option<String>,
+    suffix: Option<String>,
+) -> PyResult<()> {
+    let affix = prefix.is_some() || suffix.is_some();
+    let modes = [new_tags.is_some(), mapping.is_some(), mapping_file.is_some(), pattern.is_some(), affix];
+    if modes.iter().filter(|&&m| m).count() != 1 {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "Provide exactly one of new_tags, mapping, mapping_file, pattern or prefix/suffix.",
+        ));
+    }
+
+    let rename = if let Some(new_tags) = new_tags {
+        TagRename::List(new_tags)
+    } else if let Some(mapping) = mapping {
+        TagRename::Map(mapping)
+    } else if let Some(path) = mapping_file {
+        TagRename::from_mapping_file(&path).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?
+    } else if let Some(pattern) = pattern {
+        let pattern = regex::Regex::new(&pattern)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        TagRename::Regex { pattern, replacement: replacement.unwrap_or_default() }
+    } else {
+        TagRename::Affix { prefix: prefix.unwrap_or_default(), suffix: suffix.unwrap_or_default() }
+    };
+
+    let mut qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let builtins = py.import("builtins")?;
+    let changed = match qv.core.rename_tags(&rename) {
+        Ok(changed) => changed,
+        Err(e) => {
+            builtins.getattr("print")?.call1((format!("❌ {}", e),))?;
+            return Err(pyo3::exceptions::PyValueError::new_err(e.to_string()));
+        }
+    };
+
+    builtins.getattr("print")?.call1((
+        format!("✅ Successfully renamed {} tags in {}", changed, quiver_file),
+    ))?;
+    Ok(())
+}
+
+// qvslice 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, tags=None, match_mode="exact", invert=false, ordered=false, output=None))]
+fn qvslice(
+    py: Python,
+    quiver_file: String,
+    tags: Option<Vec<String>>,
+    match_mode: &str,
+    invert: bool,
+    ordered: bool,
+    output: Option<String>,
+) -> PyResult<()> {
+    let mut tag_list = tags.unwrap_or_default();
+
+    // Read tags from stdin if no arguments are provided
+    if tag_list.is_empty() {
+        let stdin = io::stdin();
+        let mut stdin_reader = stdin.lock();
+        let mut stdin_data = Vec::new();
+        match stdin_reader.read_to_end(&mut stdin_data) {
+            Ok(_) => {
+                let stdin_str = String::from_utf8_lossy(&stdin_data);
+                tag_list.extend(stdin_str.split_whitespace().map(String::from));
+            }
+            Err(e) => {
+                let builtins = py.import("builtins")?;
+                builtins.getattr("print")?.call1((
+                    format!("❌ Error reading from stdin: {}", e),
+                ))?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Clean and validate tag list
+    tag_list.retain(|tag| !tag.trim().is_empty());
+    if tag_list.is_empty() {
+        let builtins = py.import("builtins")?;
+        builtins.getattr("print")?.call1((
+            "❌ No tags provided. Provide tags as arguments or via stdin.",
+        ))?;
+        return Ok(());
+    }
+
+    let matcher = TagMatcher::new(&tag_list, match_mode, invert)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+
+    let mut qv_bytes = Vec::new();
+    let found_tags = match &output {
+        Some(path) => {
+            let mut out = io::BufWriter::new(
+                File::create(path).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+            );
+            let found = qv.core.write_slice(&matcher, ordered, &mut out)
+                .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+            out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+            found
+        }
+        None => qv.core.write_slice(&matcher, ordered, &mut qv_bytes)
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    };
+
+    let builtins = py.import("builtins")?;
+
+    // Warn about missing tags
+    if match_mode == "exact" && !invert {
+        let tag_set: HashSet<_> = found_tags.iter().collect();
+        for tag in &tag_list {
+            if !tag_set.contains(tag) {
+                builtins.getattr("print")?.call1((format!("⚠️  Tag not found in Quiver file: {}", tag),))?;
+            }
+        }
+    }
+
+    // Output sliced content
+    match output {
+        Some(path) => {
+            builtins.getattr("print")?.call1((
+                format!("✅ Wrote {} tags to {}", found_tags.len(), path),
+            ))?;
+        }
+        None => {
+            builtins.getattr("print")?.call1((String::from_utf8_lossy(&qv_bytes).to_string(),))?;
+        }
+    }
+    Ok(())
+}
+
+// qvsplit 함수 추가
+#[pyfunction]
+fn qvsplit(py: Python, file: String, ntags: usize, prefix: String, output_dir: String) -> PyResult<()> {
+    if ntags == 0 {
+        let builtins = py.import("builtins")?;
+        builtins.getattr("print")?.call1(("❌ NTAGS must be a positive integer.",))?;
+        return Err(pyo3::exceptions::PyValueError::new_err("NTAGS must be a positive integer."));
+    }
+
+    match Quiver::new(file.clone(), "r".to_string(), None, false, false) {
+        Ok(q) => {
+            match q.split(ntags, output_dir.clone(), prefix.clone()) {
+                Ok(_) => {
+                    let builtins = py.import("builtins")?;
+                    builtins.getattr("print")?.call1((
+                        format!("✅ Files written to {} with prefix '{}'", output_dir, prefix),
+                    ))?;
+                    Ok(())
+                }
+                Err(e) => Err(pyo3::exceptions::PyIOError::new_err(e)),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// qvverify 함수 추가
+#[pyfunction]
+fn qvverify<'py>(py: Python<'py>, quiver_file: String) -> PyResult<Bound<'py, PyList>> {
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let results = qv.core.verify().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    let problems = PyList::empty(py);
+    let mut unchecked = 0;
+    for result in &results {
+        match result.status {
+            checksum::VerifyStatus::Ok => continue,
+            checksum::VerifyStatus::Unchecked => {
+                unchecked += 1;
+                continue;
+            }
+            _ => {}
+        }
+        builtins.getattr("print")?.call1((
+            format!("❌ {} at byte {}: {}", result.tag, result.offset, result.status.name()),
+        ))?;
+        let problem = PyDict::new(py);
+        problem.set_item("tag", &result.tag)?;
+        problem.set_item("offset", result.offset)?;
+        problem.set_item("status", result.status.name())?;
+        problem.set_item("expected", &result.expected)?;
+        problem.set_item("actual", &result.actual)?;
+        problems.append(problem)?;
+    }
+
+    if unchecked > 0 {
+        builtins.getattr("print")?.call1((
+            format!("⚠️  {} records have no checksum", unchecked),
+        ))?;
+    }
+    if problems.is_empty() {
+        builtins.getattr("print")?.call1((
+            format!("✅ Verified {} records in {}", results.len() - unchecked, quiver_file),
+        ))?;
+    }
+    Ok(problems)
+}
+
+// qvrepair 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, rename_duplicates=false))]
+fn qvrepair<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    output: String,
+    rename_duplicates: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    if quiver_file == output {
+        return Err(pyo3::exceptions::PyValueError::new_err("Output file must differ from the input."));
+    }
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let report = qv.core.repair(rename_duplicates, &mut out)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    let dropped = PyList::empty(py);
+    for record in &report.dropped {
+        builtins.getattr("print")?.call1((
+            format!("⚠️  Dropped {} at byte {}: {}", record.tag, record.offset, record.reason),
+        ))?;
+        let entry = PyDict::new(py);
+        entry.set_item("tag", &record.tag)?;
+        entry.set_item("offset", record.offset)?;
+        entry.set_item("reason", &record.reason)?;
+        dropped.append(entry)?;
+    }
+    for (old, new) in &report.renamed {
+        builtins.getattr("print")?.call1((format!("⚠️  Renamed duplicate {} to {}", old, new),))?;
+    }
+    if !report.orphan_annotations.is_empty() {
+        builtins.getattr("print")?.call1((
+            format!("⚠️  Removed {} orphan score/metadata lines", report.orphan_annotations.len()),
+        ))?;
+    }
+    builtins.getattr("print")?.call1((
+        format!("✅ Recovered {} records from {} into {}", report.kept, quiver_file, output),
+    ))?;
+
+    let result = PyDict::new(py);
+    result.set_item("kept", report.kept)?;
+    result.set_item("dropped", dropped)?;
+    result.set_item("renamed", report.renamed)?;
+    result.set_item("orphan_annotations", report.orphan_annotations)?;
+    result.set_item("stray_lines", report.stray_lines)?;
+    Ok(result)
+}
+
+// qvcat 함수 추가
+#[pyfunction]
+fn qvcat(py: Python, quiver_files: Vec<String>, output: String) -> PyResult<()> {
+    if quiver_files.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err("No input Quiver files provided."));
+    }
+    if quiver_files.contains(&output) {
+        return Err(pyo3::exceptions::PyValueError::new_err("Output file must not be one of the inputs."));
+    }
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let count = QuiverCore::merge(&quiver_files, &mut out)
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Merged {} tags from {} files into {}", count, quiver_files.len(), output),
+    ))?;
+    Ok(())
+}
+
+// qvsample 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, n=None, fraction=None, seed=0, group_regex=None, score_term=None, bin_width=1.0))]
+#[allow(clippy::too_many_arguments)]
+fn qvsample(
+    py: Python,
+    quiver_file: String,
+    output: String,
+    n: Option<usize>,
+    fraction: Option<f64>,
+    seed: u64,
+    group_regex: Option<String>,
+    score_term: Option<String>,
+    bin_width: f64,
+) -> PyResult<()> {
+    let size = match (n, fraction) {
+        (Some(n), None) => SampleSize::Count(n),
+        (None, Some(fraction)) => SampleSize::Fraction(fraction),
+        _ => {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Provide exactly one of n or fraction.",
+            ))
+        }
+    };
+    let strata = match (group_regex, score_term) {
+        (None, None) => Strata::None,
+        (Some(pattern), None) => Strata::TagPattern(
+            regex::Regex::new(&pattern)
+                .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?,
+        ),
+        (None, Some(term)) => {
+            if bin_width <= 0.0 {
+                return Err(pyo3::exceptions::PyValueError::new_err("bin_width must be positive."));
+            }
+            Strata::ScoreBin { term, width: bin_width }
+        }
+        _ => {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "Stratify by either group_regex or score_term, not both.",
+            ))
+        }
+    };
+
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let sampled = qv.core.sample(size, &strata, seed, &mut out)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Sampled {} of {} tags from {} into {}", sampled.len(), qv.core.size(), quiver_file, output),
+    ))?;
+    Ok(())
+}
+
+// qvsort 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, score_term=None, natural=false, reverse=false))]
+fn qvsort(
+    py: Python,
+    quiver_file: String,
+    output: String,
+    score_term: Option<String>,
+    natural: bool,
+    reverse: bool,
+) -> PyResult<()> {
+    let key = match score_term {
+        Some(term) => SortKey::Score(term),
+        None => SortKey::Tag { natural },
+    };
+
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let count = qv.core.sort_records(&key, reverse, &mut out)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Sorted {} tags from {} into {}", count, quiver_file, output),
+    ))?;
+    Ok(())
+}
+
+// qvdedup 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output, by_structure=false))]
+fn qvdedup(py: Python, quiver_file: String, output: String, by_structure: bool) -> PyResult<Vec<String>> {
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let dropped = qv.core.dedup(by_structure, &mut out)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    for tag in &dropped {
+        builtins.getattr("print")?.call1((format!("⚠️  Dropped duplicate: {}", tag),))?;
+    }
+    builtins.getattr("print")?.call1((
+        format!("✅ Removed {} duplicates from {} into {}", dropped.len(), quiver_file, output),
+    ))?;
+    Ok(dropped)
+}
+
+// qvselect 함수 추가
+/// Write a new Quiver file keeping only the atoms matching `selection` in
+/// each record, e.g. `"chain A"` to drop a binder chain. Records with no
+/// selected atoms are left out; their tags are returned.
+#[pyfunction]
+fn qvselect(py: Python, quiver_file: String, selection: String, output: String) -> PyResult<Vec<String>> {
+    let selection = Selection::parse(&selection).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let (written, emptied) = qv.core.select_atoms(&selection, &mut out)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    for tag in &emptied {
+        builtins.getattr("print")?.call1((format!("⚠️  No atoms selected in {}, skipping", tag),))?;
+    }
+    builtins.getattr("print")?.call1((
+        format!("✅ Wrote {} records with '{}' from {} to {}", written, selection, quiver_file, output),
+    ))?;
+    Ok(emptied)
+}
+
+// qvtransform 함수 추가
+/// Write a new Quiver file with per-record transforms applied, in this
+/// order: strip hydrogens / HETATM / waters, rename chains by `rechain`
+/// (e.g. `{"B": "A"}`), renumber residues from 1 per chain, recenter on the
+/// origin and rewrite atom serials. Records left without atoms are skipped;
+/// their tags are returned.
+#[pyfunction]
+#[pyo3(signature = (
+    quiver_file, output, renumber=false, rechain=None, strip_hydrogens=false, strip_hetatm=false,
+    strip_waters=false, recenter=false, reserial=false
+))]
+#[allow(clippy::too_many_arguments)]
+fn qvtransform(
+    py: Python,
+    quiver_file: String,
+    output: String,
+    renumber: bool,
+    rechain: Option<HashMap<String, String>>,
+    strip_hydrogens: bool,
+    strip_hetatm: bool,
+    strip_waters: bool,
+    recenter: bool,
+    reserial: bool,
+) -> PyResult<Vec<String>> {
+    let chain_id = |id: &str| {
+        let mut chars = id.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Chain IDs are single characters, not '{}'", id
+            ))),
+        }
+    };
+    let mut chain_map = HashMap::new();
+    for (from, to) in rechain.unwrap_or_default() {
+        chain_map.insert(chain_id(&from)?, chain_id(&to)?);
+    }
+    let transform = Transform {
+        keep: transform::strip_selection(strip_hydrogens, strip_hetatm, strip_waters)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?,
+        rechain: chain_map,
+        renumber,
+        recenter,
+        reserial,
+    };
+    if transform.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err("No transform requested"));
+    }
+
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let mut out = io::BufWriter::new(
+        File::create(&output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+    );
+    let (written, emptied) = qv.core.transform(&transform, &mut out).map_err(|e| match e {
+        QuiverError::Invalid(_) => pyo3::exceptions::PyValueError::new_err(e.to_string()),
+        _ => pyo3::exceptions::PyIOError::new_err(e.to_string()),
+    })?;
+    out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    for tag in &emptied {
+        builtins.getattr("print")?.call1((format!("⚠️  No atoms left in {}, skipping", tag),))?;
+    }
+    builtins.getattr("print")?.call1((
+        format!("✅ Transformed {} records from {} into {}", written, quiver_file, output),
+    ))?;
+    Ok(emptied)
+}
+
+// qvdiff 함수 추가
+/// Compare two Quiver files. Returns a dict with the tags `only_a` and
+/// `only_b`, `structures` (tag and coordinate RMSD, or None when the atoms
+/// differ) whose PDB text differs, `scores` (tag, term, value in A, value
+/// in B) that differ and `identical`. With `rmsd_tolerance`, text changes
+/// within that coordinate RMSD are ignored.
+#[pyfunction]
+#[pyo3(signature = (quiver_a, quiver_b, rmsd_tolerance=None, scores=true))]
+fn qvdiff<'py>(
+    py: Python<'py>,
+    quiver_a: String,
+    quiver_b: String,
+    rmsd_tolerance: Option<f64>,
+    scores: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let qv_a = Quiver::new(quiver_a.clone(), "r".to_string(), None, false, false)?;
+    let qv_b = Quiver::new(quiver_b.clone(), "r".to_string(), None, false, false)?;
+    let diff = py
+        .detach(|| qv_a.core.diff(&qv_b.core, rmsd_tolerance, scores))
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    let print = builtins.getattr("print")?;
+    for tag in &diff.only_a {
+        print.call1((format!("⚠️  Only in {}: {}", quiver_a, tag),))?;
+    }
+    for tag in &diff.only_b {
+        print.call1((format!("⚠️  Only in {}: {}", quiver_b, tag),))?;
+    }
+    for structure in &diff.structures {
+        let detail = match structure.rmsd {
+            Some(rmsd) => format!("coordinate RMSD {:.3}", rmsd),
+            None => "atoms differ".to_string(),
+        };
+        print.call1((format!("⚠️  Structure differs: {} ({})", structure.tag, detail),))?;
+    }
+    for score in &diff.scores {
+        print.call1((format!(
+            "⚠️  Score differs: {} {}: {} != {}",
+            score.tag,
+            score.term,
+            score.a.as_deref().unwrap_or("-"),
+            score.b.as_deref().unwrap_or("-"),
+        ),))?;
+    }
+    if diff.is_empty() {
+        print.call1((format!("✅ {} and {} are identical", quiver_a, quiver_b),))?;
+    } else {
+        print.call1((format!(
+            "❌ {} only in A, {} only in B, {} structures and {} scores differ",
+            diff.only_a.len(), diff.only_b.len(), diff.structures.len(), diff.scores.len()
+        ),))?;
+    }
+
+    let result = PyDict::new(py);
+    result.set_item("identical", diff.is_empty())?;
+    result.set_item("only_a", &diff.only_a)?;
+    result.set_item("only_b", &diff.only_b)?;
+    let structures: Vec<(String, Option<f64>)> = diff.structures.iter().map(|s| (s.tag.clone(), s.rmsd)).collect();
+    result.set_item("structures", structures)?;
+    let score_diffs: Vec<(String, String, Option<String>, Option<String>)> = diff
+        .scores
+        .into_iter()
+        .map(|s| (s.tag, s.term, s.a, s.b))
+        .collect();
+    result.set_item("scores", score_diffs)?;
+    Ok(result)
+}
+
+// qvcluster 함수 추가
+/// Cluster records by CA-RMSD (`threshold` in Å) or TM-score (`threshold`
+/// as the minimum score). Returns a dict with `tags`, their `cluster` ids
+/// and the `representatives` of each cluster; with `output`, the
+/// representatives are also written to a new Quiver file. Records are
+/// considered in ascending `score_term` order, or descending with
+/// `descending=True`.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, threshold, metric="rmsd", method="leader", tags=None, score_term=None, descending=false, output=None))]
+#[allow(clippy::too_many_arguments)]
+fn qvcluster<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    threshold: f64,
+    metric: &str,
+    method: &str,
+    tags: Option<Vec<String>>,
+    score_term: Option<String>,
+    descending: bool,
+    output: Option<String>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let metric = ClusterMetric::from_name(metric, threshold).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let method = ClusterMethod::from_name(method).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let tags = tags.unwrap_or_else(|| qv.core.get_tags());
+    let clustering = py
+        .detach(|| qv.core.cluster(&tags, metric, method, score_term.as_deref(), descending))
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    if let Some(output) = &output {
+        let matcher = TagMatcher::new(&clustering.representatives, "exact", false)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let mut out = io::BufWriter::new(
+            File::create(output).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?,
+        );
+        qv.core.write_slice(&matcher, true, &mut out).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        out.flush().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        builtins.getattr("print")?.call1((format!(
+            "✅ Wrote {} representatives to {}", clustering.representatives.len(), output
+        ),))?;
+    }
+    builtins.getattr("print")?.call1((format!(
+        "✅ Clustered {} tags from {} into {} clusters", clustering.tags.len(), quiver_file, clustering.representatives.len()
+    ),))?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("tags", &clustering.tags)?;
+    dict.set_item("cluster", &clustering.cluster)?;
+    dict.set_item("representatives", &clustering.representatives)?;
+    Ok(dict)
+}
+
+/// A score table as a dict of column name -> list of values, with numeric
+/// columns as floats.
+fn table_to_dict<'py>(py: Python<'py>, table: &ScoreTable) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (col, name) in table.columns.iter().enumerate() {
+        let column = PyList::empty(py);
+        if table.is_numeric(col) {
+            for row in &table.rows {
+                column.append(row[col].as_deref().and_then(|v| v.parse::<f64>().ok()))?;
+            }
+        } else {
+            for row in &table.rows {
+                column.append(row[col].as_deref())?;
+            }
+        }
+        dict.set_item(name, column)?;
+    }
+    Ok(dict)
+}
+
+// extract_scorefile 함수 추가
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output=None, format=None, return_table=false))]
+fn extract_scorefile<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    output: Option<String>,
+    format: Option<String>,
+    return_table: bool,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let table = qv.core.score_table().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    if table.rows.is_empty() {
+        let builtins = py.import("builtins")?;
+        builtins.getattr("print")?.call1(("❌ No score lines found in Quiver file.",))?;
+        return Err(pyo3::exceptions::PyValueError::new_err("No score lines found in Quiver file."));
+    }
+
+    // 테이블을 파이썬 dict로 반환 (열 이름 -> 값 리스트)
+    if return_table {
+        return Ok(Some(table_to_dict(py, &table)?));
+    }
+
+    let format = match format {
+        Some(name) => ScoreFormat::from_name(&name).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?,
+        None => output.as_deref().map(ScoreFormat::from_path).unwrap_or(ScoreFormat::Tsv),
+    };
+    let outfn = match output {
+        Some(path) => path,
+        None => {
+            Path::new(&quiver_file).with_extension(format.extension()).to_str()
+                .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("Invalid file path"))?
+                .to_string()
+        }
+    };
+    table.write(&outfn, format).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+    let builtins = py.import("builtins")?;
+    builtins.getattr("print")?.call1((
+        format!("✅ Scorefile written to: {}", outfn),
+    ))?;
+
+    Ok(None)
+}
+
+// rmsd_to_reference 함수 추가
+/// RMSD of every record (or of `tags`) to a reference PDB file, as a NumPy
+/// array in tag order. With `append_scores`, the values are also written as
+/// the `score_term` term of each record's `QV_SCORE` line.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, reference, atoms="ca", tags=None, append_scores=false, score_term="rmsd"))]
+fn rmsd_to_reference<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    reference: String,
+    atoms: &str,
+    tags: Option<Vec<String>>,
+    append_scores: bool,
+    score_term: &str,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    let selection = Selection::for_atoms(atoms).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let reference_lines: Vec<String> = std::fs::read_to_string(&reference)
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(format!("{}: {}", reference, e)))?
+        .lines()
+        .map(String::from)
+        .collect();
+    let reference = Coords::select(&pdb::parse_atoms(&reference_lines), &selection);
+
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let tags = tags.unwrap_or_else(|| qv.core.get_tags());
+    let values = py
+        .detach(|| qv.core.rmsd_to_reference(&reference, &tags, &selection))
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+
+    if append_scores {
+        let terms: HashMap<String, Vec<(String, String)>> = tags
+            .iter()
+            .zip(&values)
+            .map(|(tag, rmsd)| (tag.clone(), vec![(score_term.to_string(), format!("{:.3}", rmsd))]))
+            .collect();
+        let mut target = Quiver::new(quiver_file.clone(), "w".to_string(), None, false, false)?;
+        let updated = target.core.add_score_terms(&terms).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        let builtins = py.import("builtins")?;
+        builtins.getattr("print")?.call1((format!("✅ Added {} to {} records in {}", score_term, updated, quiver_file),))?;
+    }
+    Ok(PyArray1::from_vec(py, values))
+}
+
+// rmsd_matrix 함수 추가
+/// All-vs-all RMSD over every record (or `tags`) as an `n x n` NumPy array,
+/// computed in parallel.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, tags=None, atoms="ca"))]
+fn rmsd_matrix<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    tags: Option<Vec<String>>,
+    atoms: &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let selection = Selection::for_atoms(atoms).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let qv = Quiver::new(quiver_file, "r".to_string(), None, false, false)?;
+    let tags = tags.unwrap_or_else(|| qv.core.get_tags());
+    let matrix = py
+        .detach(|| qv.core.rmsd_matrix(&tags, &selection))
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    Ok(PyArray1::from_vec(py, matrix).reshape([tags.len(), tags.len()])?.into_any())
+}
+
+/// Write a per-record table to `output`, or next to the Quiver file as
+/// `<name>.<kind>.sc` (or `.csv`, `.jsonl`, `.parquet`). Returns the path.
+fn write_table(
+    table: &ScoreTable,
+    quiver_file: &str,
+    kind: &str,
+    output: Option<String>,
+    format: Option<String>,
+) -> PyResult<String> {
+    let format = match format {
+        Some(name) => ScoreFormat::from_name(&name).map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?,
+        None => output.as_deref().map(ScoreFormat::from_path).unwrap_or(ScoreFormat::Tsv),
+    };
+    let outfn = match output {
+        Some(path) => path,
+        None => {
+            Path::new(quiver_file).with_extension(format!("{}.{}", kind, format.extension())).to_str()
+                .ok_or_else(|| pyo3::exceptions::PyValueError::new_err("Invalid file path"))?
+                .to_string()
+        }
+    };
+    table.write(&outfn, format).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    Ok(outfn)
+}
+
+// qvstats 함수 추가
+/// Per-record structural statistics. Written as a table to `output` (format
+/// from `format` or the extension), merged into each record's `QV_SCORE`
+/// line with `append_scores`, and/or returned as a dict with `return_table`.
+/// Without any of these, the table is written next to the Quiver file.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output=None, format=None, append_scores=false, return_table=false))]
+fn qvstats<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    output: Option<String>,
+    format: Option<String>,
+    append_scores: bool,
+    return_table: bool,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let stats = qv.core.stats().map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    let table = stats::stats_table(&stats);
+    let builtins = py.import("builtins")?;
+
+    if output.is_some() || format.is_some() || !(append_scores || return_table) {
+        let outfn = write_table(&table, &quiver_file, "stats", output, format)?;
+        builtins.getattr("print")?.call1((format!("✅ Stats written to: {}", outfn),))?;
+    }
+
+    if append_scores {
+        let terms: HashMap<String, Vec<(String, String)>> =
+            stats.iter().map(|(tag, stats)| (tag.clone(), stats.to_pairs())).collect();
+        let mut target = Quiver::new(quiver_file.clone(), "w".to_string(), None, false, false)?;
+        let updated = target.core.add_score_terms(&terms).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        builtins.getattr("print")?.call1((format!("✅ Added stats to {} records in {}", updated, quiver_file),))?;
+    }
+
+    if return_table {
+        return Ok(Some(table_to_dict(py, &table)?));
+    }
+    Ok(None)
+}
+
+// qvdssp 함수 추가
+/// DSSP-style secondary structure of every record: the per-residue string
+/// (`H G I E B T S`, `-` for loop) and helix, strand and loop fractions.
+/// Output options are as in `qvstats`; the table is written next to the
+/// Quiver file as `.dssp.sc` by default.
+#[pyfunction]
+#[pyo3(signature = (quiver_file, output=None, format=None, append_scores=false, return_table=false))]
+fn qvdssp<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    output: Option<String>,
+    format: Option<String>,
+    append_scores: bool,
+    return_table: bool,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let assignments = py
+        .detach(|| qv.core.secondary_structure())
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    let table = dssp::dssp_table(&assignments);
+    let builtins = py.import("builtins")?;
+
+    if output.is_some() || format.is_some() || !(append_scores || return_table) {
+        let outfn = write_table(&table, &quiver_file, "dssp", output, format)?;
+        builtins.getattr("print")?.call1((format!("✅ Secondary structure written to: {}", outfn),))?;
+    }
+
+    if append_scores {
+        let terms: HashMap<String, Vec<(String, String)>> =
+            assignments.iter().map(|(tag, ss)| (tag.clone(), ss.to_pairs())).collect();
+        let mut target = Quiver::new(quiver_file.clone(), "w".to_string(), None, false, false)?;
+        let updated = target.core.add_score_terms(&terms).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        builtins.getattr("print")?.call1((
+            format!("✅ Added secondary structure to {} records in {}", updated, quiver_file),
+        ))?;
+    }
+
+    if return_table {
+        return Ok(Some(table_to_dict(py, &table)?));
+    }
+    Ok(None)
+}
+
+// qvinterface 함수 추가
+/// Inter-chain interface metrics of every record: residue and atom contacts
+/// within `contact_cutoff`, heavy-atom clashes below `clash_cutoff` and the
+/// interface residues of each chain. `chains` such as `"A:B"` or `"AB:C"`
+/// limits the analysis to one interface; by default every pair of chains
+/// is compared. Output options are as in `qvstats`.
+#[pyfunction]
+#[pyo3(signature = (
+    quiver_file, contact_cutoff=interface::DEFAULT_CONTACT_CUTOFF, clash_cutoff=interface::DEFAULT_CLASH_CUTOFF,
+    chains=None, output=None, format=None, append_scores=false, return_table=false
+))]
+#[allow(clippy::too_many_arguments)]
+fn qvinterface<'py>(
+    py: Python<'py>,
+    quiver_file: String,
+    contact_cutoff: f64,
+    clash_cutoff: f64,
+    chains: Option<String>,
+    output: Option<String>,
+    format: Option<String>,
+    append_scores: bool,
+    return_table: bool,
+) -> PyResult<Option<Bound<'py, PyDict>>> {
+    if contact_cutoff <= 0.0 || clash_cutoff <= 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err("Cutoffs must be positive"));
+    }
+    let split = chains
+        .as_deref()
+        .map(ChainSplit::parse)
+        .transpose()
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let qv = Quiver::new(quiver_file.clone(), "r".to_string(), None, false, false)?;
+    let stats = py
+        .detach(|| qv.core.interfaces(split.as_ref(), contact_cutoff, clash_cutoff))
+        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+    let table = interface::interface_table(&stats);
+    let builtins = py.import("builtins")?;
+
+    if output.is_some() || format.is_some() || !(append_scores || return_table) {
+        let outfn = write_table(&table, &quiver_file, "interface", output, format)?;
+        builtins.getattr("print")?.call1((format!("✅ Interface metrics written to: {}", outfn),))?;
+    }
+
+    if append_scores {
+        let terms: HashMap<String, Vec<(String, String)>> =
+            stats.iter().map(|(tag, stats)| (tag.clone(), stats.to_pairs())).collect();
+        let mut target = Quiver::new(quiver_file.clone(), "w".to_string(), None, false, false)?;
+        let updated = target.core.add_score_terms(&terms).map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        builtins.getattr("print")?.call1((
+            format!("✅ Added interface metrics to {} records in {}", updated, quiver_file),
+        ))?;
+    }
+
+    if return_table {
+        return Ok(Some(table_to_dict(py, &table)?));
+    }
+    Ok(None)
+}
+
+/// A Python module implemented in Rust.
+#[pymodule]
+fn quiver_pdb(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(qvfrompdbs, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_pdbs, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_multimodel_pdb, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_mmcif, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_tar, m)?)?;
+    m.add_function(wrap_pyfunction!(to_arrow, m)?)?;
+    m.add_function(wrap_pyfunction!(list_tags, m)?)?;
+    m.add_function(wrap_pyfunction!(rename_tags, m)?)?;
+    m.add_function(wrap_pyfunction!(qvslice, m)?)?;
+    m.add_function(wrap_pyfunction!(qvsplit, m)?)?;
+    m.add_function(wrap_pyfunction!(qvverify, m)?)?;
+    m.add_function(wrap_pyfunction!(qvrepair, m)?)?;
+    m.add_function(wrap_pyfunction!(qvcat, m)?)?;
+    m.add_function(wrap_pyfunction!(qvsample, m)?)?;
+    m.add_function(wrap_pyfunction!(qvsort, m)?)?;
+    m.add_function(wrap_pyfunction!(qvdedup, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_scorefile, m)?)?;
+    m.add_function(wrap_pyfunction!(qvstats, m)?)?;
+    m.add_function(wrap_pyfunction!(qvdssp, m)?)?;
+    m.add_function(wrap_pyfunction!(qvinterface, m)?)?;
+    m.add_function(wrap_pyfunction!(rmsd_to_reference, m)?)?;
+    m.add_function(wrap_pyfunction!(rmsd_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(qvselect, m)?)?;
+    m.add_function(wrap_pyfunction!(qvtransform, m)?)?;
+    m.add_function(wrap_pyfunction!(qvdiff, m)?)?;
+    m.add_function(wrap_pyfunction!(qvcluster, m)?)?;
+    m.add_class::<Quiver>()?;
+    m.add_class::<PyQuiverWriter>()?;
+    m.add_class::<RecordBuffer>()?;
+    m.add_class::<ArrowStream>()?;
+    m.add("SCORE_ENCODING_VERSION", score::SCORE_ENCODING_VERSION)?;
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Tag renaming: positional lists, old→new mappings, regex substitution and
+//! prefix/suffix namespacing.
+
+use crate::mapped::MappedArchive;
+use crate::{is_annotation, QuiverCore, QuiverError, Result};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+/// How new tags are derived from the present ones.
+#[derive(Debug)]
+pub enum TagRename {
+    /// One new tag per present tag, in file order.
+    List(Vec<String>),
+    /// Rename only the listed tags; all others are left untouched.
+    Map(HashMap<String, String>),
+    /// Regex search/replace over every tag (`$1`-style group references).
+    Regex { pattern: Regex, replacement: String },
+    /// Namespace every tag as `prefix + tag + suffix`.
+    Affix { prefix: String, suffix: String },
+}
+
+impl TagRename {
+    /// Read a two-column `old new` mapping file. Blank lines and lines
+    /// starting with `#` are skipped.
+    pub fn from_mapping_file(path: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        let mut mapping = HashMap::new();
+        for (lineno, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.len() != 2 {
+                return Err(QuiverError::Invalid(format!(
+                    "Line {} of {} must have two columns (old new): {}", lineno + 1, path, line
+                )));
+            }
+            if mapping.insert(parts[0].to_string(), parts[1].to_string()).is_some() {
+                return Err(QuiverError::Invalid(format!("Tag {} is mapped more than once in {}", parts[0], path)));
+            }
+        }
+        Ok(TagRename::Map(mapping))
+    }
+
+    /// Compute the new tag for every present tag and check the result is a
+    /// valid, collision-free tag list. Nothing is written here.
+    pub fn plan(&self, present: &[String]) -> Result<Vec<String>> {
+        let new_tags: Vec<String> = match self {
+            TagRename::List(new_tags) => {
+                if present.len() != new_tags.len() {
+                    return Err(QuiverError::Invalid(format!(
+                        "Number of tags in file ({}) does not match number of tags provided ({})",
+                        present.len(),
+                        new_tags.len()
+                    )));
+                }
+                new_tags.clone()
+            }
+            TagRename::Map(mapping) => {
+                let present_set: HashSet<&String> = present.iter().collect();
+                let mut missing: Vec<&String> = mapping.keys().filter(|old| !present_set.contains(old)).collect();
+                if !missing.is_empty() {
+                    missing.sort();
+                    return Err(QuiverError::Invalid(format!(
+                        "Tags in mapping not found in Quiver file: {}",
+                        missing.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", ")
+                    )));
+                }
+                present.iter().map(|tag| mapping.get(tag).unwrap_or(tag).clone()).collect()
+            }
+            TagRename::Regex { pattern, replacement } => present
+                .iter()
+                .map(|tag| pattern.replace_all(tag, replacement.as_str()).into_owned())
+                .collect(),
+            TagRename::Affix { prefix, suffix } => present
+                .iter()
+                .map(|tag| format!("{}{}{}", prefix, tag, suffix))
+                .collect(),
+        };
+
+        let mut seen = HashSet::new();
+        for (old, new) in present.iter().zip(&new_tags) {
+            if new.is_empty() || new.contains(char::is_whitespace) {
+                return Err(QuiverError::Invalid(format!("Invalid new tag for {}: '{}'", old, new)));
+            }
+            if !seen.insert(new.as_str()) {
+                return Err(QuiverError::Invalid(format!("Renaming would create duplicate tag: {}", new)));
+            }
+        }
+        Ok(new_tags)
+    }
+}
+
+impl QuiverCore {
+    /// Rename tags in place. The full rename is validated first, then the file
+    /// is rewritten through a temporary file next to it. Returns the number of
+    /// tags that changed.
+    pub fn rename_tags(&mut self, rename: &TagRename) -> Result<usize> {
+        let new_tags = rename.plan(&self.tags)?;
+
+        let tmp_path = format!("{}.rename.tmp", self.fnm);
+        let reader = BufReader::new(File::open(&self.fnm)?);
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+
+        let mut tag_idx = 0;
+        let mut current: Option<(&str, &str)> = None;
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with("QV_TAG") {
+                let new = new_tags
+                    .get(tag_idx)
+                    .ok_or_else(|| QuiverError::Invalid(format!("{} changed while renaming", self.fnm)))?;
+                current = Some((self.tags[tag_idx].as_str(), new.as_str()));
+                tag_idx += 1;
+                writeln!(out, "QV_TAG {}", new)?;
+                continue;
+            }
+            if is_annotation(line.as_bytes()) {
+                let mut parts = line.splitn(3, ' ');
+                let (key, tag, rest) = (parts.next(), parts.next(), parts.next());
+                if let (Some(key), Some((old, new)), Some(tag)) = (key, current, tag) {
+                    if tag == old {
+                        writeln!(out, "{} {} {}", key, new, rest.unwrap_or(""))?;
+                        continue;
+                    }
+                }
+            }
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()?;
+        drop(out);
+        std::fs::rename(&tmp_path, &self.fnm)?;
+        if self.mapped.is_some() {
+            self.mapped = Some(MappedArchive::open(&self.fnm)?);
+        }
+
+        let changed = self.tags.iter().zip(&new_tags).filter(|(old, new)| old != new).count();
+        self.tags = new_tags;
+        Ok(changed)
+    }
+}
@@ -0,0 +1,164 @@
+//! Recovery of usable records from truncated or corrupted Quiver files.
+
+use crate::checksum::{verify_record, VerifyStatus};
+use crate::{is_annotation, QuiverCore, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+/// A record left out of the repaired file.
+#[derive(Debug, Clone)]
+pub struct DroppedRecord {
+    pub tag: String,
+    /// Byte offset of the record's `QV_TAG` line in the damaged file.
+    pub offset: u64,
+    pub reason: String,
+}
+
+/// What `repair` changed.
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    pub kept: usize,
+    pub dropped: Vec<DroppedRecord>,
+    /// Duplicate tags kept under a new name, as `(old, new)`.
+    pub renamed: Vec<(String, String)>,
+    /// Byte offsets of score, metadata or checksum lines that did not belong to the
+    /// record they appeared in.
+    pub orphan_annotations: Vec<u64>,
+    /// Byte offsets of non-header lines found before the first `QV_TAG`.
+    pub stray_lines: Vec<u64>,
+}
+
+struct RawRecord {
+    tag: String,
+    offset: u64,
+    /// `(offset, bytes)` of every line after the `QV_TAG` line.
+    lines: Vec<(u64, Vec<u8>)>,
+}
+
+impl QuiverCore {
+    /// Scan the file and write every intact record to `out`. Records that are
+    /// truncated, fail their checksum or have no PDB lines are dropped; later
+    /// duplicates of a tag are dropped, or renamed `<tag>_dupN` when
+    /// `rename_duplicates` is set. Score and metadata lines naming another
+    /// tag are removed.
+    pub fn repair<W: Write>(&self, rename_duplicates: bool, out: &mut W) -> Result<RepairReport> {
+        let file = File::open(&self.fnm)?;
+        let mut reader = BufReader::new(file);
+        let mut report = RepairReport::default();
+        let mut seen = HashSet::new();
+        let mut current: Option<RawRecord> = None;
+        let mut pos = 0u64;
+        let mut buf = Vec::new();
+
+        self.write_header(out)?;
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            if n == 0 {
+                break;
+            }
+            if buf.starts_with(b"QV_TAG") {
+                if let Some(record) = current.take() {
+                    self.repair_record(record, &mut seen, rename_duplicates, &mut report, out)?;
+                }
+                let line = String::from_utf8_lossy(&buf);
+                let tag = line.split_whitespace().nth(1).unwrap_or("").to_string();
+                current = Some(RawRecord { tag, offset: pos, lines: Vec::new() });
+            } else if let Some(record) = current.as_mut() {
+                record.lines.push((pos, buf.clone()));
+            } else if !buf.starts_with(b"QV_HEADER") && !buf.iter().all(u8::is_ascii_whitespace) {
+                report.stray_lines.push(pos);
+            }
+            pos += n as u64;
+        }
+        if let Some(record) = current.take() {
+            self.repair_record(record, &mut seen, rename_duplicates, &mut report, out)?;
+        }
+        Ok(report)
+    }
+
+    fn repair_record<W: Write>(
+        &self,
+        record: RawRecord,
+        seen: &mut HashSet<String>,
+        rename_duplicates: bool,
+        report: &mut RepairReport,
+        out: &mut W,
+    ) -> Result<()> {
+        let dropped = |report: &mut RepairReport, reason: &str| {
+            report.dropped.push(DroppedRecord {
+                tag: record.tag.clone(),
+                offset: record.offset,
+                reason: reason.to_string(),
+            });
+        };
+
+        if record.tag.is_empty() {
+            dropped(report, "missing tag");
+            return Ok(());
+        }
+        if record.lines.last().is_some_and(|(_, line)| !line.ends_with(b"\n")) {
+            dropped(report, "truncated");
+            return Ok(());
+        }
+
+        // Keep only annotations that name this record.
+        let mut kept_lines: Vec<&[u8]> = Vec::with_capacity(record.lines.len());
+        let mut has_payload = false;
+        for (offset, line) in &record.lines {
+            if is_annotation(line) && !line.starts_with(b"QV_HEADER") {
+                let text = String::from_utf8_lossy(line);
+                if text.split_whitespace().nth(1) != Some(record.tag.as_str()) {
+                    report.orphan_annotations.push(*offset);
+                    continue;
+                }
+            } else if line.starts_with(b"QV_HEADER") {
+                continue;
+            } else if !line.iter().all(u8::is_ascii_whitespace) {
+                has_payload = true;
+            }
+            kept_lines.push(line);
+        }
+        if !has_payload {
+            dropped(report, "no PDB lines");
+            return Ok(());
+        }
+
+        let raw: Vec<u8> = kept_lines.concat();
+        let status = verify_record(&record.tag, record.offset, &raw).status;
+        if matches!(status, VerifyStatus::Truncated | VerifyStatus::Mismatch) {
+            dropped(report, &format!("checksum {}", status.name()));
+            return Ok(());
+        }
+
+        let mut tag = record.tag.clone();
+        if !seen.insert(tag.clone()) {
+            if !rename_duplicates {
+                dropped(report, "duplicate tag");
+                return Ok(());
+            }
+            let mut n = 1;
+            while seen.contains(&format!("{}_dup{}", record.tag, n)) {
+                n += 1;
+            }
+            tag = format!("{}_dup{}", record.tag, n);
+            seen.insert(tag.clone());
+            report.renamed.push((record.tag.clone(), tag.clone()));
+        }
+
+        writeln!(out, "QV_TAG {}", tag)?;
+        for line in kept_lines {
+            if tag != record.tag && is_annotation(line) {
+                let text = String::from_utf8_lossy(line);
+                let mut parts = text.splitn(3, ' ');
+                let (key, rest) = (parts.next().unwrap_or(""), parts.nth(1).unwrap_or("\n"));
+                write!(out, "{} {} {}", key, tag, rest)?;
+            } else {
+                out.write_all(line)?;
+            }
+        }
+        report.kept += 1;
+        Ok(())
+    }
+}
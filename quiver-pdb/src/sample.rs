@@ -0,0 +1,154 @@
+//! Random and stratified subsampling of Quiver records.
+
+use crate::score::score_value;
+use crate::{QuiverCore, QuiverError, RecordOffset, Result};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+/// How many records to draw, overall or per stratum.
+#[derive(Debug, Clone, Copy)]
+pub enum SampleSize {
+    Count(usize),
+    Fraction(f64),
+}
+
+/// How records are grouped before sampling. Each group is sampled
+/// independently with the same `SampleSize`.
+#[derive(Debug)]
+pub enum Strata {
+    None,
+    /// Group by the first capture group of a regex over the tag (or the whole
+    /// match when the regex has no groups). Unmatched tags form a group of
+    /// their own.
+    TagPattern(Regex),
+    /// Group by `floor(value / width)` of a score term. Records without the
+    /// term form a group of their own.
+    ScoreBin { term: String, width: f64 },
+}
+
+impl Strata {
+    /// Group of one record; `None` for unmatched tags and missing terms, so
+    /// they never share a group with an empty capture.
+    fn key(&self, tag: &str, score: Option<&str>) -> Option<String> {
+        match self {
+            Strata::None => Some(String::new()),
+            Strata::TagPattern(re) => re
+                .captures(tag)
+                .and_then(|caps| caps.get(1).or_else(|| caps.get(0)))
+                .map(|m| m.as_str().to_string()),
+            Strata::ScoreBin { term, width } => score
+                .and_then(|score| score_value(score, term))
+                .map(|value| ((value / width).floor() as i64).to_string()),
+        }
+    }
+}
+
+/// Uniform sample of at most `k` items from a stream of unknown length
+/// (Algorithm R).
+#[derive(Debug)]
+pub(crate) struct Reservoir<T> {
+    k: usize,
+    seen: usize,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    pub fn new(k: usize) -> Self {
+        Reservoir { k, seen: 0, items: Vec::new() }
+    }
+
+    pub fn push(&mut self, item: T, rng: &mut StdRng) {
+        if self.seen < self.k {
+            self.items.push(item);
+        } else {
+            let j = rng.random_range(0..=self.seen);
+            if j < self.k {
+                self.items[j] = item;
+            }
+        }
+        self.seen += 1;
+    }
+
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl QuiverCore {
+    /// Scan the file once, passing the byte range and the `QV_SCORE` value
+    /// of each record to `visit`.
+    fn scan_scores(&self, mut visit: impl FnMut(RecordOffset, Option<&str>)) -> Result<()> {
+        let mut reader = BufReader::new(File::open(&self.fnm)?);
+        let mut current: Option<(RecordOffset, Option<String>)> = None;
+        let mut pos = 0u64;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            if n == 0 || buf.starts_with(b"QV_TAG") {
+                if let Some((mut offset, score)) = current.take() {
+                    offset.end = pos;
+                    visit(offset, score.as_deref());
+                }
+            }
+            if n == 0 {
+                return Ok(());
+            }
+            let field = |i| String::from_utf8_lossy(&buf).split_whitespace().nth(i).map(String::from);
+            if buf.starts_with(b"QV_TAG") {
+                let tag = field(1).unwrap_or_default();
+                current = Some((RecordOffset { tag, start: pos, end: pos }, None));
+            } else if buf.starts_with(b"QV_SCORE") {
+                if let Some((_, score)) = current.as_mut() {
+                    *score = field(2);
+                }
+            }
+            pos += n as u64;
+        }
+    }
+
+    /// Write a random subset of the records to `out`, keeping file order.
+    /// The file is read once: with a count, each stratum fills a reservoir
+    /// as its records stream past; with a fraction, the records of each
+    /// stratum are collected and exactly `round(fraction * n)` of its `n`
+    /// records are drawn. Returns the tags written.
+    pub fn sample<W: Write>(&self, size: SampleSize, strata: &Strata, seed: u64, out: &mut W) -> Result<Vec<String>> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
+        }
+        if let SampleSize::Fraction(fraction) = size {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(QuiverError::Invalid(format!("Sample fraction must be between 0 and 1, not {}", fraction)));
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut reservoirs: HashMap<Option<String>, Reservoir<RecordOffset>> = HashMap::new();
+        // Ordered by key, so the draw for a seed does not depend on hashing.
+        let mut groups: BTreeMap<Option<String>, Vec<RecordOffset>> = BTreeMap::new();
+        self.scan_scores(|offset, score| {
+            let key = strata.key(&offset.tag, score);
+            match size {
+                SampleSize::Count(k) => reservoirs.entry(key).or_insert_with(|| Reservoir::new(k)).push(offset, &mut rng),
+                SampleSize::Fraction(_) => groups.entry(key).or_default().push(offset),
+            }
+        })?;
+        let mut selected: Vec<RecordOffset> = reservoirs.into_values().flat_map(Reservoir::into_items).collect();
+        if let SampleSize::Fraction(fraction) = size {
+            for members in groups.into_values() {
+                let k = (fraction * members.len() as f64).round() as usize;
+                selected.extend(members.choose_multiple(&mut rng, k).cloned());
+            }
+        }
+        selected.sort_unstable_by_key(|offset| offset.start);
+
+        self.write_header(out)?;
+        self.copy_records(&selected, out)?;
+        Ok(selected.into_iter().map(|offset| offset.tag).collect())
+    }
+}
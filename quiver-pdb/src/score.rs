@@ -0,0 +1,198 @@
+//! Encoding of `QV_SCORE` and `QV_META` strings (`key=value|key=value`).
+//!
+//! Version 1 wrote keys and values verbatim, so they could not contain
+//! whitespace, `|` or `=`. Version 2 percent-escapes those characters (and
+//! `%` itself) in both keys and values. Decoding is backwards compatible:
+//! version 1 strings decode unchanged unless they contain `%XX` sequences.
+
+use crate::{is_annotation, lock_archive, QuiverCore, QuiverError, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+/// Current score/metadata string encoding version.
+pub const SCORE_ENCODING_VERSION: u32 = 2;
+
+/// Percent-escape a key or value so it can be embedded in a score string.
+pub(crate) fn escape_field(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '%' | '|' | '=' => out.push_str(&format!("%{:02X}", c as u32)),
+            c if c.is_ascii_whitespace() || c.is_ascii_control() => {
+                out.push_str(&format!("%{:02X}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Undo [`escape_field`]. Malformed escapes are kept as written.
+pub(crate) fn unescape_field(field: &str) -> String {
+    if !field.contains('%') {
+        return field.to_string();
+    }
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Serialize `(key, value)` pairs into a score string.
+pub fn encode_score<K: AsRef<str>, V: AsRef<str>>(pairs: &[(K, V)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", escape_field(key.as_ref()), escape_field(value.as_ref())))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Split a score string into decoded `(key, value)` pairs in the order
+/// written. Entries without exactly one `=` are ignored.
+pub fn parse_score_str(score: &str) -> Vec<(String, String)> {
+    score
+        .split('|')
+        .filter_map(|entry| {
+            let mut parts = entry.split('=');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(key), Some(value), None) => Some((unescape_field(key), unescape_field(value))),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Look up a single numeric score term.
+pub fn score_value(score: &str, term: &str) -> Option<f64> {
+    parse_score_str(score)
+        .into_iter()
+        .find(|(key, _)| key == term)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// Replace or add `(key, value)` pairs in a score string, keeping the
+/// original order of existing keys.
+pub(crate) fn merge_score(score: &str, terms: &[(String, String)]) -> String {
+    let mut pairs = parse_score_str(score);
+    for (key, value) in terms {
+        match pairs.iter_mut().find(|(k, _)| k == key) {
+            Some(pair) => pair.1 = value.clone(),
+            None => pairs.push((key.clone(), value.clone())),
+        }
+    }
+    encode_score(&pairs)
+}
+
+impl QuiverCore {
+    /// Merge score terms into the `QV_SCORE` lines of the given tags, adding
+    /// a `QV_SCORE` line where a record has none. The file must be open for
+    /// writing. It is rewritten through a temporary file next to it, which is
+    /// renamed over the original while the archive's lock is held, so locking
+    /// writers wait for the rewrite and open readers keep the old file.
+    /// Returns the number of records updated.
+    pub fn add_score_terms(&mut self, terms: &HashMap<String, Vec<(String, String)>>) -> Result<usize> {
+        if self.mode != "w" {
+            return Err(QuiverError::write_mode());
+        }
+        // The lock is released when `_lock` is dropped at the end of this call.
+        let _lock = lock_archive(&self.fnm)?;
+        let tmp_path = format!("{}.scores.tmp", self.fnm);
+        let reader = BufReader::new(File::open(&self.fnm)?);
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+
+        let mut tags = Vec::new();
+        let mut updated = 0;
+        let mut current = String::new();
+        let mut pending: Option<&Vec<(String, String)>> = None;
+        for line in reader.lines() {
+            let line = line?;
+            if line.starts_with("QV_SCORE") {
+                if let Some(new_terms) = pending {
+                    let mut parts = line.splitn(3, ' ');
+                    if parts.nth(1) == Some(current.as_str()) {
+                        let merged = merge_score(parts.next().unwrap_or("").trim(), new_terms);
+                        writeln!(out, "QV_SCORE {} {}", current, merged)?;
+                        pending = None;
+                        continue;
+                    }
+                }
+            } else if !is_annotation(line.as_bytes()) {
+                // New terms go after the leading annotations of a record.
+                if let Some(new_terms) = pending.take() {
+                    writeln!(out, "QV_SCORE {} {}", current, encode_score(new_terms))?;
+                }
+            }
+            if line.starts_with("QV_TAG") {
+                current = line.split_whitespace().nth(1).unwrap_or("").to_string();
+                pending = terms.get(&current);
+                updated += usize::from(pending.is_some());
+                tags.push(current.clone());
+            }
+            writeln!(out, "{}", line)?;
+        }
+        if let Some(new_terms) = pending {
+            writeln!(out, "QV_SCORE {} {}", current, encode_score(new_terms))?;
+        }
+        out.flush()?;
+        drop(out);
+        std::fs::rename(&tmp_path, &self.fnm)?;
+        self.known_len = std::fs::metadata(&self.fnm)?.len();
+        self.tags = tags;
+        Ok(updated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_field_round_trips() {
+        for field in ["plain", "a b", "x=1|y=2", "100%", "tab\there", "line\nbreak", "%41", "ünïcode", ""] {
+            let escaped = escape_field(field);
+            assert!(!escaped.contains(['|', '=', ' ', '\t', '\n']), "{:?}", escaped);
+            assert_eq!(unescape_field(&escaped), field);
+        }
+    }
+
+    #[test]
+    fn parse_score_str_round_trips_encode_score() {
+        let pairs = vec![
+            ("ddg".to_string(), "-12.5".to_string()),
+            ("note".to_string(), "two words|and=signs".to_string()),
+            ("key with space".to_string(), "50%".to_string()),
+        ];
+        assert_eq!(parse_score_str(&encode_score(&pairs)), pairs);
+    }
+
+    #[test]
+    fn parse_score_str_reads_version_1_strings() {
+        let pairs = parse_score_str("rms=1.5|score=0.8|broken|a=b=c");
+        assert_eq!(pairs, [("rms".to_string(), "1.5".to_string()), ("score".to_string(), "0.8".to_string())]);
+        // Malformed escapes are kept as written.
+        assert_eq!(parse_score_str("path=%zz%4")[0].1, "%zz%4");
+    }
+
+    #[test]
+    fn score_value_and_merge_score() {
+        assert_eq!(score_value("ddg=-12.5|name=x", "ddg"), Some(-12.5));
+        assert_eq!(score_value("ddg=-12.5|name=x", "name"), None);
+        assert_eq!(score_value("ddg=-12.5", "plddt"), None);
+
+        let merged = merge_score("ddg=1|plddt=80", &[("ddg".to_string(), "2".to_string()), ("rg".to_string(), "9".to_string())]);
+        assert_eq!(merged, "ddg=2|plddt=80|rg=9");
+    }
+}
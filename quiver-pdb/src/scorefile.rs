@@ -0,0 +1,228 @@
+//! Score tables built from `QV_SCORE` lines and their on-disk formats.
+
+use crate::score::parse_score_str;
+use crate::{QuiverCore, QuiverError, Result};
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Output formats for score tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreFormat {
+    Tsv,
+    Csv,
+    JsonLines,
+    Parquet,
+}
+
+impl ScoreFormat {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "tsv" | "sc" => Ok(ScoreFormat::Tsv),
+            "csv" => Ok(ScoreFormat::Csv),
+            "json" | "jsonl" => Ok(ScoreFormat::JsonLines),
+            "parquet" => Ok(ScoreFormat::Parquet),
+            _ => Err(QuiverError::Invalid(format!(
+                "Score format must be one of tsv, csv, jsonl or parquet, not '{}'", name
+            ))),
+        }
+    }
+
+    /// File extension used for default output paths.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScoreFormat::Tsv => "sc",
+            ScoreFormat::Csv => "csv",
+            ScoreFormat::JsonLines => "jsonl",
+            ScoreFormat::Parquet => "parquet",
+        }
+    }
+
+    /// Guess the format from a file extension, defaulting to TSV.
+    pub fn from_path(path: &str) -> Self {
+        let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+        ScoreFormat::from_name(&ext.to_ascii_lowercase()).unwrap_or(ScoreFormat::Tsv)
+    }
+}
+
+/// Scores of every record with a `QV_SCORE` line. Columns are ordered by
+/// first appearance, with `tag` first; values keep their original text. A
+/// score term named `tag` becomes the column `score_tag` (and `score_tag`
+/// becomes `score_score_tag`), so it cannot overwrite the record's tag.
+#[derive(Debug, Default)]
+pub struct ScoreTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quote a field holding the separator, a quote or a line break, doubling
+/// inner quotes; CSV and TSV output both use this.
+fn delimited_field(s: &str, sep: char) -> String {
+    if s.contains([sep, '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+impl ScoreTable {
+    /// A column is numeric when every present value parses as a float. The
+    /// `tag` column is always text.
+    pub fn is_numeric(&self, col: usize) -> bool {
+        col != 0
+            && self
+                .rows
+                .iter()
+                .filter_map(|row| row[col].as_deref())
+                .all(|value| value.parse::<f64>().is_ok())
+    }
+
+    fn write_delimited<W: Write>(&self, out: &mut W, sep: char) -> Result<()> {
+        let field = |s: &str| delimited_field(s, sep);
+        let numeric: Vec<bool> = (0..self.columns.len()).map(|col| self.is_numeric(col)).collect();
+        let header: Vec<String> = self.columns.iter().map(|c| field(c)).collect();
+        writeln!(out, "{}", header.join(&sep.to_string()))?;
+        for row in &self.rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&numeric)
+                .map(|(value, &numeric)| match value {
+                    Some(value) => field(value),
+                    None if numeric => "NaN".to_string(),
+                    None => String::new(),
+                })
+                .collect();
+            writeln!(out, "{}", cells.join(&sep.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn write_json_lines<W: Write>(&self, out: &mut W) -> Result<()> {
+        let numeric: Vec<bool> = (0..self.columns.len()).map(|col| self.is_numeric(col)).collect();
+        for row in &self.rows {
+            let fields: Vec<String> = self
+                .columns
+                .iter()
+                .zip(row)
+                .zip(&numeric)
+                .map(|((column, value), &numeric)| {
+                    let value = match value {
+                        None => "null".to_string(),
+                        Some(value) if numeric => match value.parse::<f64>() {
+                            Ok(number) if number.is_finite() => number.to_string(),
+                            _ => "null".to_string(),
+                        },
+                        Some(value) => json_string(value),
+                    };
+                    format!("{}: {}", json_string(column), value)
+                })
+                .collect();
+            writeln!(out, "{{{}}}", fields.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// Build an Arrow record batch with Float64 columns for numeric scores
+    /// and Utf8 columns for everything else.
+    pub fn to_record_batch(&self) -> Result<RecordBatch> {
+        let mut fields = Vec::with_capacity(self.columns.len());
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(self.columns.len());
+        for (col, name) in self.columns.iter().enumerate() {
+            let values = self.rows.iter().map(|row| row[col].as_deref());
+            if self.is_numeric(col) {
+                fields.push(Field::new(name, DataType::Float64, true));
+                let array: Float64Array = values.map(|v| v.and_then(|v| v.parse().ok())).collect();
+                arrays.push(Arc::new(array));
+            } else {
+                fields.push(Field::new(name, DataType::Utf8, col != 0));
+                let array: StringArray = values.collect();
+                arrays.push(Arc::new(array));
+            }
+        }
+        Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)?)
+    }
+
+    pub fn write(&self, path: &str, format: ScoreFormat) -> Result<()> {
+        let file = File::create(path)?;
+        if format == ScoreFormat::Parquet {
+            let batch = self.to_record_batch()?;
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            return Ok(());
+        }
+        let mut out = BufWriter::new(file);
+        match format {
+            ScoreFormat::Tsv => self.write_delimited(&mut out, '\t')?,
+            ScoreFormat::Csv => self.write_delimited(&mut out, ',')?,
+            ScoreFormat::JsonLines => self.write_json_lines(&mut out)?,
+            ScoreFormat::Parquet => unreachable!(),
+        }
+        Ok(out.flush()?)
+    }
+}
+
+/// Column name of a score term, kept apart from the `tag` column.
+fn term_column(term: String) -> String {
+    if term.trim_start_matches("score_") == "tag" {
+        format!("score_{}", term)
+    } else {
+        term
+    }
+}
+
+impl QuiverCore {
+    /// Collect the scores of every record that has a `QV_SCORE` line.
+    pub fn score_table(&self) -> Result<ScoreTable> {
+        let mut table = ScoreTable { columns: vec!["tag".to_string()], rows: Vec::new() };
+        let mut index: HashMap<String, usize> = HashMap::new();
+        index.insert("tag".to_string(), 0);
+
+        for record in self.records()? {
+            let record = record?;
+            let Some(score) = record.score else { continue };
+            let mut row = vec![None; table.columns.len()];
+            row[0] = Some(record.tag);
+            for (key, value) in parse_score_str(&score) {
+                let key = term_column(key);
+                let col = *index.entry(key.clone()).or_insert_with(|| {
+                    table.columns.push(key);
+                    table.columns.len() - 1
+                });
+                if col >= row.len() {
+                    row.resize(col + 1, None);
+                }
+                row[col] = Some(value);
+            }
+            table.rows.push(row);
+        }
+        let width = table.columns.len();
+        for row in &mut table.rows {
+            row.resize(width, None);
+        }
+        Ok(table)
+    }
+}
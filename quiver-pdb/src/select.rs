@@ -0,0 +1,282 @@
+//! A small atom selection language and the `qvselect` transform.
+//!
+//! ```text
+//! chain A and resi 10-50 and name CA
+//! chain A+B and not hydrogen
+//! (resn HIS or resn CYS) and not backbone
+//! ```
+//!
+//! Selectors take one or more values separated by spaces, `+` or `,`:
+//! `chain`, `resi`/`resid` (numbers or `start-end` ranges), `resn`/`resname`,
+//! `name` and `element`/`elem`. Flags: `all`, `none`, `hetatm`, `water`,
+//! `hydrogen`, `backbone`. Combine with `and`, `or`, `not` and parentheses.
+//! Residue and atom names and elements match case-insensitively; chain IDs are
+//! case-sensitive.
+
+use crate::pdb::Atom;
+use crate::{QuiverCore, QuiverError, Result};
+use std::fmt;
+use std::io::Write;
+
+#[derive(Debug, Clone)]
+enum Expr {
+    All,
+    Nothing,
+    Hetatm,
+    Water,
+    Hydrogen,
+    Backbone,
+    Chain(Vec<char>),
+    Resi(Vec<(i64, i64)>),
+    Resn(Vec<String>),
+    Name(Vec<String>),
+    Element(Vec<String>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn matches(&self, atom: &Atom) -> bool {
+        match self {
+            Expr::All => true,
+            Expr::Nothing => false,
+            Expr::Hetatm => atom.hetatm,
+            Expr::Water => atom.is_water(),
+            Expr::Hydrogen => matches!(atom.element.to_ascii_uppercase().as_str(), "H" | "D"),
+            Expr::Backbone => !atom.hetatm && matches!(atom.name.as_str(), "N" | "CA" | "C" | "O"),
+            Expr::Chain(chains) => chains.contains(&atom.chain),
+            Expr::Resi(ranges) => ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&atom.res_seq)),
+            Expr::Resn(names) => names.iter().any(|n| n.eq_ignore_ascii_case(&atom.res_name)),
+            Expr::Name(names) => names.iter().any(|n| n.eq_ignore_ascii_case(&atom.name)),
+            Expr::Element(elements) => elements.iter().any(|e| e.eq_ignore_ascii_case(&atom.element)),
+            Expr::Not(inner) => !inner.matches(atom),
+            Expr::And(a, b) => a.matches(atom) && b.matches(atom),
+            Expr::Or(a, b) => a.matches(atom) || b.matches(atom),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn is_reserved(token: &str) -> bool {
+    matches!(
+        token.to_ascii_lowercase().as_str(),
+        "and" | "or" | "not" | "(" | ")" | "chain" | "resi" | "resid" | "resn" | "resname" | "name" | "element"
+            | "elem" | "all" | "none" | "hetatm" | "water" | "hydrogen" | "backbone"
+    )
+}
+
+fn parse_range(value: &str) -> Result<(i64, i64)> {
+    // The separator is a '-' after the first character, so "-5--1" works.
+    let split = value.char_indices().skip(1).find(|&(_, c)| c == '-').map(|(i, _)| i);
+    let parse = |s: &str| s.parse::<i64>().map_err(|_| QuiverError::Invalid(format!("Invalid residue number '{}'", s)));
+    match split {
+        Some(i) => {
+            let (lo, hi) = (parse(&value[..i])?, parse(&value[i + 1..])?);
+            if lo > hi {
+                return Err(QuiverError::Invalid(format!("Invalid residue range '{}'", value)));
+            }
+            Ok((lo, hi))
+        }
+        None => parse(value).map(|n| (n, n)),
+    }
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<String> {
+        self.tokens.get(self.pos).map(|t| t.to_ascii_lowercase())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        while self.peek().as_deref() == Some("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.not_expr()?;
+        while self.peek().as_deref() == Some("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.peek().as_deref() == Some("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        self.primary()
+    }
+
+    /// Values following a selector, up to the next reserved word.
+    fn values(&mut self, keyword: &str) -> Result<Vec<String>> {
+        let mut values = Vec::new();
+        while let Some(token) = self.tokens.get(self.pos) {
+            if is_reserved(token) {
+                break;
+            }
+            values.extend(token.split(['+', ',']).filter(|v| !v.is_empty()).map(String::from));
+            self.pos += 1;
+        }
+        if values.is_empty() {
+            return Err(QuiverError::Invalid(format!("'{}' needs at least one value", keyword)));
+        }
+        Ok(values)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let Some(token) = self.next() else {
+            return Err(QuiverError::Invalid("Selection ended unexpectedly".to_string()));
+        };
+        let keyword = token.to_ascii_lowercase();
+        Ok(match keyword.as_str() {
+            "(" => {
+                let expr = self.or_expr()?;
+                if self.next().as_deref() != Some(")") {
+                    return Err(QuiverError::Invalid("Missing ')' in selection".to_string()));
+                }
+                expr
+            }
+            "all" => Expr::All,
+            "none" => Expr::Nothing,
+            "hetatm" => Expr::Hetatm,
+            "water" => Expr::Water,
+            "hydrogen" => Expr::Hydrogen,
+            "backbone" => Expr::Backbone,
+            "chain" => {
+                let values = self.values(&keyword)?;
+                let mut chains = Vec::with_capacity(values.len());
+                for value in values {
+                    let mut chars = value.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => chains.push(c),
+                        _ => return Err(QuiverError::Invalid(format!("Chain IDs are single characters, not '{}'", value))),
+                    }
+                }
+                Expr::Chain(chains)
+            }
+            "resi" | "resid" => {
+                Expr::Resi(self.values(&keyword)?.iter().map(|v| parse_range(v)).collect::<Result<_, _>>()?)
+            }
+            "resn" | "resname" => Expr::Resn(self.values(&keyword)?),
+            "name" => Expr::Name(self.values(&keyword)?),
+            "element" | "elem" => Expr::Element(self.values(&keyword)?),
+            _ => return Err(QuiverError::Invalid(format!("Unexpected '{}' in selection", token))),
+        })
+    }
+}
+
+/// A parsed atom selection.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    text: String,
+    expr: Expr,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl Selection {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser { tokens: tokenize(text), pos: 0 };
+        if parser.tokens.is_empty() {
+            return Err(QuiverError::Invalid("Empty selection".to_string()));
+        }
+        let expr = parser.or_expr().map_err(|e| QuiverError::Invalid(format!("{} in '{}'", e, text)))?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(QuiverError::Invalid(format!("Unexpected '{}' in '{}'", token, text)));
+        }
+        Ok(Selection { text: text.to_string(), expr })
+    }
+
+    /// Superposition presets (`ca`, `backbone`/`bb`, `heavy`) or any
+    /// selection expression.
+    pub fn for_atoms(text: &str) -> Result<Self> {
+        match text {
+            "ca" => Selection::parse("name CA and not hetatm"),
+            "bb" => Selection::parse("backbone"),
+            "heavy" => Selection::parse("not hydrogen"),
+            _ => Selection::parse(text),
+        }
+    }
+
+    pub fn matches(&self, atom: &Atom) -> bool {
+        self.expr.matches(atom)
+    }
+
+    /// PDB lines of the selected atoms. `ANISOU` and `TER` lines follow the
+    /// atom before them; `CONECT` lines are dropped since they may refer to
+    /// removed atoms; all other lines are kept.
+    pub fn filter_lines(&self, lines: &[String]) -> Vec<String> {
+        let mut kept = Vec::with_capacity(lines.len());
+        let mut last_atom_kept = false;
+        for line in lines {
+            if line.starts_with("ATOM") || line.starts_with("HETATM") {
+                last_atom_kept = Atom::parse(line).is_some_and(|atom| self.matches(&atom));
+                if last_atom_kept {
+                    kept.push(line.clone());
+                }
+            } else if line.starts_with("ANISOU") || line.starts_with("SIGUIJ") {
+                if last_atom_kept {
+                    kept.push(line.clone());
+                }
+            } else if line.starts_with("TER") {
+                if last_atom_kept {
+                    kept.push(line.clone());
+                }
+                last_atom_kept = false;
+            } else if !line.starts_with("CONECT") {
+                kept.push(line.clone());
+            }
+        }
+        kept
+    }
+}
+
+impl QuiverCore {
+    /// Write every record to `out` keeping only the atoms in `selection`.
+    /// Records left without atoms are skipped; see
+    /// [`QuiverCore::rewrite_records`].
+    pub fn select_atoms<W: Write>(&self, selection: &Selection, out: &mut W) -> Result<(usize, Vec<String>)> {
+        self.rewrite_records(out, |record| Ok(selection.filter_lines(&record.lines)))
+    }
+}
@@ -0,0 +1,166 @@
+//! Reordering and deduplication of Quiver records.
+//!
+//! Only a small key per record (tag, byte range, score or hash) is kept in
+//! memory; the record text itself is copied from the input by seeking, so
+//! archives larger than memory can be processed.
+
+use crate::pdb::parse_atoms;
+use crate::score::score_value;
+use crate::{QuiverCore, QuiverError, RecordOffset, Result};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+/// What records are ordered by.
+#[derive(Debug)]
+pub enum SortKey {
+    /// By tag, comparing digit runs numerically when `natural` is set
+    /// (`design_2` before `design_10`).
+    Tag { natural: bool },
+    /// By a numeric `QV_SCORE` term. Records without it sort last.
+    Score(String),
+}
+
+/// Compare two strings treating runs of ASCII digits as numbers.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    while !a.is_empty() && !b.is_empty() {
+        if a[0].is_ascii_digit() && b[0].is_ascii_digit() {
+            let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            let a_num = trim_leading_zeros(&a[..a_len]);
+            let b_num = trim_leading_zeros(&b[..b_len]);
+            let ord = a_num.len().cmp(&b_num.len()).then_with(|| a_num.cmp(b_num));
+            if ord != Ordering::Equal {
+                return ord;
+            }
+            a = &a[a_len..];
+            b = &b[b_len..];
+        } else {
+            if a[0] != b[0] {
+                return a[0].cmp(&b[0]);
+            }
+            a = &a[1..];
+            b = &b[1..];
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+/// Atom coordinates of a record, rounded to PDB precision.
+fn coordinates(lines: &[String]) -> Vec<[i64; 3]> {
+    parse_atoms(lines)
+        .iter()
+        .map(|atom| [atom.x, atom.y, atom.z].map(|coord| (coord * 1000.0).round() as i64))
+        .collect()
+}
+
+fn coordinate_hash(coords: &[[i64; 3]]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    coords.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl QuiverCore {
+    /// Write all records to `out` sorted by `key`. The sort is stable, so
+    /// records with equal keys keep their file order.
+    pub fn sort_records<W: Write>(&self, key: &SortKey, reverse: bool, out: &mut W) -> Result<usize> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
+        }
+        let mut offsets = self.record_offsets()?;
+        match key {
+            SortKey::Tag { natural } => {
+                let cmp: fn(&str, &str) -> Ordering = if *natural { natural_cmp } else { str::cmp };
+                offsets.sort_by(|a, b| {
+                    let ord = cmp(&a.tag, &b.tag);
+                    if reverse { ord.reverse() } else { ord }
+                });
+            }
+            SortKey::Score(term) => {
+                let mut values = Vec::with_capacity(offsets.len());
+                for record in self.records()? {
+                    let record = record?;
+                    values.push(record.score.as_deref().and_then(|score| score_value(score, term)));
+                }
+                let mut keyed: Vec<(Option<f64>, RecordOffset)> = values.into_iter().zip(offsets).collect();
+                keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+                    (Some(a), Some(b)) => {
+                        let ord = a.total_cmp(b);
+                        if reverse { ord.reverse() } else { ord }
+                    }
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                });
+                offsets = keyed.into_iter().map(|(_, offset)| offset).collect();
+            }
+        }
+        self.write_header(out)?;
+        self.copy_records(&offsets, out)?;
+        Ok(offsets.len())
+    }
+
+    /// Write records to `out`, dropping any whose tag was already seen and,
+    /// with `by_structure`, any whose coordinates match an earlier record.
+    /// Records without atoms are never structure duplicates. The first
+    /// occurrence is kept. Returns the tags that were dropped.
+    pub fn dedup<W: Write>(&self, by_structure: bool, out: &mut W) -> Result<Vec<String>> {
+        if self.mode != "r" {
+            return Err(QuiverError::read_mode());
+        }
+        let offsets = self.record_offsets()?;
+        let mut records = if by_structure { Some(self.records()?) } else { None };
+
+        let mut seen_tags = HashSet::new();
+        // Coordinate hash -> indices into `kept`. Only hashes are held in
+        // memory; on a hit the earlier record is read back and compared.
+        let mut seen_structures: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut kept: Vec<RecordOffset> = Vec::new();
+        let mut dropped = Vec::new();
+        for offset in offsets {
+            let coords = match records.as_mut().and_then(|records| records.next()) {
+                Some(record) => coordinates(&record?.lines),
+                None => Vec::new(),
+            };
+            let mut duplicate = !seen_tags.insert(offset.tag.clone());
+            let mut hash = None;
+            if !duplicate && !coords.is_empty() {
+                let key = coordinate_hash(&coords);
+                for &index in seen_structures.get(&key).into_iter().flatten() {
+                    if self.record_coordinates(&kept[index])? == coords {
+                        duplicate = true;
+                        break;
+                    }
+                }
+                hash = Some(key);
+            }
+            if duplicate {
+                dropped.push(offset.tag);
+                continue;
+            }
+            if let Some(key) = hash {
+                seen_structures.entry(key).or_default().push(kept.len());
+            }
+            kept.push(offset);
+        }
+        self.write_header(out)?;
+        self.copy_records(&kept, out)?;
+        Ok(dropped)
+    }
+
+    /// Read one record back from the file and return its coordinates.
+    fn record_coordinates(&self, offset: &RecordOffset) -> Result<Vec<[i64; 3]>> {
+        let mut text = Vec::new();
+        self.copy_records(std::slice::from_ref(offset), &mut text)?;
+        let lines: Vec<String> = String::from_utf8_lossy(&text).lines().map(|line| format!("{}\n", line)).collect();
+        Ok(coordinates(&lines))
+    }
+}
@@ -0,0 +1,171 @@
+//! Per-record structural summary statistics for first-pass QC.
+
+use crate::pdb::{parse_atoms, Atom};
+use crate::scorefile::ScoreTable;
+use crate::{QuiverCore, Result};
+
+/// Backbone atoms every protein residue is expected to have.
+const BACKBONE: [&str; 4] = ["N", "CA", "C", "O"];
+/// Longest C–N peptide bond distance (Å) not counted as a chain break.
+const MAX_PEPTIDE_BOND: f64 = 2.0;
+/// Longest CA–CA distance (Å) between consecutive residues, used when the
+/// C or N atom is missing.
+const MAX_CA_CA: f64 = 4.2;
+
+/// Column names of [`StructStats::to_pairs`], in order.
+pub const STAT_TERMS: [&str; 8] = [
+    "n_residues",
+    "n_chains",
+    "chain_lengths",
+    "n_atoms",
+    "radius_of_gyration",
+    "mean_b_factor",
+    "missing_backbone",
+    "chain_breaks",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct StructStats {
+    /// Polymer (ATOM) residues.
+    pub residues: usize,
+    /// `(chain id, residue count)` in file order.
+    pub chain_lengths: Vec<(char, usize)>,
+    /// ATOM and HETATM records, first alternate location only.
+    pub atoms: usize,
+    pub radius_of_gyration: f64,
+    /// Mean B-factor of CA atoms (per-residue pLDDT for predicted models),
+    /// or of all atoms when there are no CA atoms.
+    pub mean_b_factor: f64,
+    /// Residues lacking any of N, CA, C or O.
+    pub missing_backbone: usize,
+    /// Gaps between consecutive residues of a chain.
+    pub chain_breaks: usize,
+}
+
+struct Residue<'a> {
+    chain: char,
+    atoms: Vec<&'a Atom>,
+}
+
+impl Residue<'_> {
+    fn atom(&self, name: &str) -> Option<&Atom> {
+        self.atoms.iter().copied().find(|atom| atom.name == name)
+    }
+}
+
+fn distance(a: &Atom, b: &Atom) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0usize), |(sum, n), v| (sum + v, n + 1));
+    if n == 0 { 0.0 } else { sum / n as f64 }
+}
+
+impl StructStats {
+    pub fn compute(atoms: &[Atom]) -> Self {
+        let atoms: Vec<&Atom> = atoms.iter().filter(|atom| matches!(atom.alt_loc, ' ' | 'A')).collect();
+
+        // Group ATOM records into residues in file order.
+        let mut residues: Vec<Residue> = Vec::new();
+        let mut last_key = None;
+        for &atom in atoms.iter().filter(|atom| !atom.hetatm) {
+            let key = (atom.chain, atom.res_seq, atom.i_code);
+            if last_key != Some(key) {
+                residues.push(Residue { chain: atom.chain, atoms: Vec::new() });
+                last_key = Some(key);
+            }
+            if let Some(residue) = residues.last_mut() {
+                residue.atoms.push(atom);
+            }
+        }
+
+        let mut stats = StructStats { residues: residues.len(), atoms: atoms.len(), ..Default::default() };
+        for (i, residue) in residues.iter().enumerate() {
+            match stats.chain_lengths.last_mut() {
+                Some((chain, len)) if *chain == residue.chain => *len += 1,
+                _ => stats.chain_lengths.push((residue.chain, 1)),
+            }
+            if BACKBONE.iter().any(|name| residue.atom(name).is_none()) {
+                stats.missing_backbone += 1;
+            }
+            let Some(next) = residues.get(i + 1).filter(|next| next.chain == residue.chain) else { continue };
+            let broken = match (residue.atom("C"), next.atom("N")) {
+                (Some(c), Some(n)) => distance(c, n) > MAX_PEPTIDE_BOND,
+                _ => match (residue.atom("CA"), next.atom("CA")) {
+                    (Some(a), Some(b)) => distance(a, b) > MAX_CA_CA,
+                    _ => true,
+                },
+            };
+            stats.chain_breaks += usize::from(broken);
+        }
+
+        if !atoms.is_empty() {
+            let center = [
+                mean(atoms.iter().map(|a| a.x)),
+                mean(atoms.iter().map(|a| a.y)),
+                mean(atoms.iter().map(|a| a.z)),
+            ];
+            let msd = mean(atoms.iter().map(|a| {
+                (a.x - center[0]).powi(2) + (a.y - center[1]).powi(2) + (a.z - center[2]).powi(2)
+            }));
+            stats.radius_of_gyration = msd.sqrt();
+        }
+        let ca: Vec<f64> = atoms.iter().filter(|a| !a.hetatm && a.name == "CA").map(|a| a.b_factor).collect();
+        stats.mean_b_factor = if ca.is_empty() {
+            mean(atoms.iter().map(|a| a.b_factor))
+        } else {
+            mean(ca.into_iter())
+        };
+        stats
+    }
+
+    /// The statistics as score terms, named as in [`STAT_TERMS`].
+    /// Chain lengths are written as `A:120,B:45`.
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let chain_lengths = self
+            .chain_lengths
+            .iter()
+            .map(|(chain, len)| format!("{}:{}", chain, len))
+            .collect::<Vec<_>>()
+            .join(",");
+        let values = [
+            self.residues.to_string(),
+            self.chain_lengths.len().to_string(),
+            chain_lengths,
+            self.atoms.to_string(),
+            format!("{:.3}", self.radius_of_gyration),
+            format!("{:.3}", self.mean_b_factor),
+            self.missing_backbone.to_string(),
+            self.chain_breaks.to_string(),
+        ];
+        STAT_TERMS.iter().map(|term| term.to_string()).zip(values).collect()
+    }
+}
+
+impl QuiverCore {
+    /// Structural statistics of every record, in file order.
+    pub fn stats(&self) -> Result<Vec<(String, StructStats)>> {
+        let mut stats = Vec::with_capacity(self.tags.len());
+        for record in self.records()? {
+            let record = record?;
+            stats.push((record.tag, StructStats::compute(&parse_atoms(&record.lines))));
+        }
+        Ok(stats)
+    }
+}
+
+/// A table with one row per record and one column per statistic.
+pub fn stats_table(stats: &[(String, StructStats)]) -> ScoreTable {
+    let mut columns = vec!["tag".to_string()];
+    columns.extend(STAT_TERMS.iter().map(|term| term.to_string()));
+    let rows = stats
+        .iter()
+        .map(|(tag, stats)| {
+            std::iter::once(Some(tag.clone()))
+                .chain(stats.to_pairs().into_iter().map(|(_, value)| Some(value)))
+                .collect()
+        })
+        .collect();
+    ScoreTable { columns, rows }
+}
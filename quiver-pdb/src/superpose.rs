@@ -0,0 +1,269 @@
+//! Optimal rigid-body superposition and RMSD between records.
+//!
+//! The Kabsch problem is solved with Horn's quaternion method: the optimal
+//! rotation is the eigenvector of the largest eigenvalue of a 4x4 symmetric
+//! matrix built from the covariance of the two centered coordinate sets.
+
+use crate::pdb::{parse_atoms, Atom};
+use crate::select::Selection;
+use crate::{QuiverCore, QuiverError, Result};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+pub(crate) type Vec3 = [f64; 3];
+
+/// Identifies an atom across two models: chain, residue number, insertion
+/// code and atom name.
+pub(crate) type AtomKey = (char, i64, char, String);
+
+/// Selected atoms of one model, keyed for pairing with another model.
+#[derive(Debug, Clone, Default)]
+pub struct Coords {
+    pub keys: Vec<AtomKey>,
+    pub xyz: Vec<Vec3>,
+}
+
+impl Coords {
+    /// The selected atoms, first alternate location only.
+    pub fn select(atoms: &[Atom], selection: &Selection) -> Self {
+        let mut coords = Coords::default();
+        for atom in atoms {
+            if matches!(atom.alt_loc, ' ' | 'A') && selection.matches(atom) {
+                coords.keys.push((atom.chain, atom.res_seq, atom.i_code, atom.name.clone()));
+                coords.xyz.push([atom.x, atom.y, atom.z]);
+            }
+        }
+        coords
+    }
+
+    /// Coordinates of the atoms present in both models, in the order of
+    /// `self`.
+    pub fn paired(&self, other: &Coords) -> (Vec<Vec3>, Vec<Vec3>) {
+        let index: HashMap<&AtomKey, usize> = other.keys.iter().enumerate().map(|(i, key)| (key, i)).collect();
+        self.keys
+            .iter()
+            .zip(&self.xyz)
+            .filter_map(|(key, xyz)| index.get(key).map(|&j| (*xyz, other.xyz[j])))
+            .unzip()
+    }
+}
+
+/// The transform that best maps `mobile` onto `target`: `R · x + t`.
+#[derive(Debug, Clone)]
+pub struct Superposition {
+    pub rmsd: f64,
+    pub rotation: [Vec3; 3],
+    pub translation: Vec3,
+    pub n_atoms: usize,
+}
+
+fn centroid(xyz: &[Vec3]) -> Vec3 {
+    let n = xyz.len() as f64;
+    let mut c = [0.0; 3];
+    for p in xyz {
+        for k in 0..3 {
+            c[k] += p[k] / n;
+        }
+    }
+    c
+}
+
+/// Eigenvector of the largest eigenvalue of a symmetric 4x4 matrix, by
+/// cyclic Jacobi rotations.
+fn max_eigen(mut a: [[f64; 4]; 4]) -> (f64, [f64; 4]) {
+    let mut v = [[0.0; 4]; 4];
+    for (i, row) in v.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    for _ in 0..50 {
+        let off: f64 = (0..4).flat_map(|p| ((p + 1)..4).map(move |q| (p, q))).map(|(p, q)| a[p][q] * a[p][q]).sum();
+        if off < 1e-22 {
+            break;
+        }
+        for p in 0..4 {
+            for q in (p + 1)..4 {
+                if a[p][q].abs() < 1e-300 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let (c, s) = (1.0 / (t * t + 1.0).sqrt(), t / (t * t + 1.0).sqrt());
+                // A <- J^T A J, then V <- V J.
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (ap, aq) = (a[p], a[q]);
+                a[p] = std::array::from_fn(|k| c * ap[k] - s * aq[k]);
+                a[q] = std::array::from_fn(|k| s * ap[k] + c * aq[k]);
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let best = (0..4).max_by(|&i, &j| a[i][i].total_cmp(&a[j][j])).unwrap_or(0);
+    (a[best][best], [v[0][best], v[1][best], v[2][best], v[3][best]])
+}
+
+/// Superpose `mobile` onto `target` (paired coordinates of equal length).
+pub(crate) fn kabsch(mobile: &[Vec3], target: &[Vec3]) -> Result<Superposition> {
+    if mobile.len() != target.len() {
+        return Err(QuiverError::Invalid(format!("Cannot superpose {} atoms onto {}", mobile.len(), target.len())));
+    }
+    if mobile.len() < 3 {
+        return Err(QuiverError::Invalid(format!("At least 3 paired atoms are needed for superposition, found {}", mobile.len())));
+    }
+    let (cm, ct) = (centroid(mobile), centroid(target));
+    let mut s = [[0.0; 3]; 3];
+    let mut norms = 0.0;
+    for (x, y) in mobile.iter().zip(target) {
+        let x = [x[0] - cm[0], x[1] - cm[1], x[2] - cm[2]];
+        let y = [y[0] - ct[0], y[1] - ct[1], y[2] - ct[2]];
+        for a in 0..3 {
+            norms += x[a] * x[a] + y[a] * y[a];
+            for b in 0..3 {
+                s[a][b] += x[a] * y[b];
+            }
+        }
+    }
+    let [[sxx, sxy, sxz], [syx, syy, syz], [szx, szy, szz]] = s;
+    let n = [
+        [sxx + syy + szz, syz - szy, szx - sxz, sxy - syx],
+        [syz - szy, sxx - syy - szz, sxy + syx, szx + sxz],
+        [szx - sxz, sxy + syx, -sxx + syy - szz, syz + szy],
+        [sxy - syx, szx + sxz, syz + szy, -sxx - syy + szz],
+    ];
+    let (lambda, [q0, q1, q2, q3]) = max_eigen(n);
+    let rotation = [
+        [q0 * q0 + q1 * q1 - q2 * q2 - q3 * q3, 2.0 * (q1 * q2 - q0 * q3), 2.0 * (q1 * q3 + q0 * q2)],
+        [2.0 * (q1 * q2 + q0 * q3), q0 * q0 - q1 * q1 + q2 * q2 - q3 * q3, 2.0 * (q2 * q3 - q0 * q1)],
+        [2.0 * (q1 * q3 - q0 * q2), 2.0 * (q2 * q3 + q0 * q1), q0 * q0 - q1 * q1 - q2 * q2 + q3 * q3],
+    ];
+    let mut translation = ct;
+    for (a, row) in rotation.iter().enumerate() {
+        translation[a] -= row[0] * cm[0] + row[1] * cm[1] + row[2] * cm[2];
+    }
+    let msd = ((norms - 2.0 * lambda) / mobile.len() as f64).max(0.0);
+    Ok(Superposition { rmsd: msd.sqrt(), rotation, translation, n_atoms: mobile.len() })
+}
+
+/// Superpose two models on the atoms they share.
+pub(crate) fn superpose(mobile: &Coords, target: &Coords) -> Result<Superposition> {
+    let (m, t) = mobile.paired(target);
+    kabsch(&m, &t)
+}
+
+/// Apply a superposition transform to one point.
+pub(crate) fn transform(sp: &Superposition, p: &Vec3) -> Vec3 {
+    let mut out = sp.translation;
+    for (a, row) in sp.rotation.iter().enumerate() {
+        out[a] += row[0] * p[0] + row[1] * p[1] + row[2] * p[2];
+    }
+    out
+}
+
+/// TM-score of `mobile` against `target` over their paired atoms (normally
+/// CA), normalized by `norm_len` residues.
+///
+/// As in the TMscore program, the superposition maximizing the score is
+/// searched for by seeding with fragments of length L, L/2 and L/4 and
+/// iteratively re-superposing on the atoms closer than d0. Returns `None`
+/// when fewer than 3 atoms pair up.
+pub(crate) fn tm_score(mobile: &Coords, target: &Coords, norm_len: usize) -> Option<f64> {
+    let (m, t) = mobile.paired(target);
+    let n = m.len();
+    if n < 3 || norm_len == 0 {
+        return None;
+    }
+    let d0 = (1.24 * (norm_len.max(19) as f64 - 15.0).cbrt() - 1.8).max(0.5);
+    let mut best: f64 = 0.0;
+    let mut seed_len = n;
+    loop {
+        let step = (seed_len / 2).max(1);
+        let mut start = 0;
+        while start + seed_len <= n {
+            let mut subset: Vec<usize> = (start..start + seed_len).collect();
+            for _ in 0..20 {
+                let sub_m: Vec<Vec3> = subset.iter().map(|&i| m[i]).collect();
+                let sub_t: Vec<Vec3> = subset.iter().map(|&i| t[i]).collect();
+                let Ok(sp) = kabsch(&sub_m, &sub_t) else { break };
+                let dist: Vec<f64> = m
+                    .iter()
+                    .zip(&t)
+                    .map(|(p, q)| {
+                        let p = transform(&sp, p);
+                        ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2) + (p[2] - q[2]).powi(2)).sqrt()
+                    })
+                    .collect();
+                let score = dist.iter().map(|d| 1.0 / (1.0 + (d / d0).powi(2))).sum::<f64>() / norm_len as f64;
+                best = best.max(score);
+
+                let mut cutoff = d0;
+                let mut next: Vec<usize> = Vec::new();
+                while next.len() < 3 && cutoff < 100.0 {
+                    next = (0..n).filter(|&i| dist[i] < cutoff).collect();
+                    cutoff += 0.5;
+                }
+                if next == subset {
+                    break;
+                }
+                subset = next;
+            }
+            start += step;
+        }
+        if seed_len <= (n / 4).max(4) {
+            break;
+        }
+        seed_len = (seed_len / 2).max((n / 4).max(4));
+    }
+    Some(best.min(1.0))
+}
+
+impl QuiverCore {
+    /// Selected atom coordinates of one record.
+    pub fn coords(&self, tag: &str, selection: &Selection) -> Result<Coords> {
+        Ok(Coords::select(&parse_atoms(&self.get_pdblines(tag)?), selection))
+    }
+
+    /// Superpose record `mobile` onto record `target`.
+    pub fn superpose(&self, mobile: &str, target: &str, selection: &Selection) -> Result<Superposition> {
+        superpose(&self.coords(mobile, selection)?, &self.coords(target, selection)?)
+            .map_err(|e| QuiverError::Invalid(format!("{} vs {}: {}", mobile, target, e)))
+    }
+
+    /// RMSD of each of `tags` to a reference model, in parallel.
+    pub fn rmsd_to_reference(&self, reference: &Coords, tags: &[String], selection: &Selection) -> Result<Vec<f64>> {
+        let coords = tags.iter().map(|tag| self.coords(tag, selection)).collect::<Result<Vec<_>, _>>()?;
+        coords
+            .par_iter()
+            .zip(tags)
+            .map(|(coords, tag)| superpose(coords, reference).map(|s| s.rmsd).map_err(|e| QuiverError::Invalid(format!("{}: {}", tag, e))))
+            .collect()
+    }
+
+    /// All-vs-all RMSD over `tags`, as a row-major `n x n` matrix. Pairs are
+    /// computed in parallel.
+    pub fn rmsd_matrix(&self, tags: &[String], selection: &Selection) -> Result<Vec<f64>> {
+        let coords = tags.iter().map(|tag| self.coords(tag, selection)).collect::<Result<Vec<_>, _>>()?;
+        let n = tags.len();
+        let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| ((i + 1)..n).map(move |j| (i, j))).collect();
+        let values = pairs
+            .par_iter()
+            .map(|&(i, j)| {
+                superpose(&coords[i], &coords[j])
+                    .map(|s| s.rmsd)
+                    .map_err(|e| QuiverError::Invalid(format!("{} vs {}: {}", tags[i], tags[j], e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut matrix = vec![0.0; n * n];
+        for (&(i, j), rmsd) in pairs.iter().zip(values) {
+            matrix[i * n + j] = rmsd;
+            matrix[j * n + i] = rmsd;
+        }
+        Ok(matrix)
+    }
+}
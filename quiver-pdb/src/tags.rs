@@ -0,0 +1,82 @@
+//! Tag selection by exact name, glob or regular expression.
+
+use crate::{QuiverError, Result};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Selects tags by a list of patterns, optionally inverted.
+#[derive(Debug)]
+pub struct TagMatcher {
+    exact: HashMap<String, usize>,
+    regexes: Vec<Regex>,
+    invert: bool,
+}
+
+/// Translate a shell-style glob (`*`, `?`, `[...]`) into an anchored regex.
+pub(crate) fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => re.push_str(".*"),
+            '?' if !in_class => re.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                re.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                re.push(']');
+            }
+            '!' if in_class && re.ends_with('[') => re.push('^'),
+            _ if in_class => re.push(c),
+            _ => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+impl TagMatcher {
+    /// Build a matcher. `mode` is one of `exact`, `glob` or `regex`; regexes
+    /// are unanchored, so use `^...$` to match whole tags.
+    pub fn new(patterns: &[String], mode: &str, invert: bool) -> Result<Self> {
+        let mut exact = HashMap::new();
+        let mut regexes = Vec::new();
+        match mode {
+            "exact" => {
+                for (idx, pattern) in patterns.iter().enumerate() {
+                    exact.entry(pattern.clone()).or_insert(idx);
+                }
+            }
+            "glob" | "regex" => {
+                for pattern in patterns {
+                    let source = if mode == "glob" { glob_to_regex(pattern) } else { pattern.clone() };
+                    let re = Regex::new(&source)
+                        .map_err(|e| QuiverError::Invalid(format!("Invalid tag pattern '{}': {}", pattern, e)))?;
+                    regexes.push(re);
+                }
+            }
+            _ => {
+                return Err(QuiverError::Invalid(format!(
+                    "Tag match mode must be 'exact', 'glob' or 'regex', not '{}'", mode
+                )))
+            }
+        }
+        Ok(TagMatcher { exact, regexes, invert })
+    }
+
+    /// Index of the first pattern selecting `tag`, or `None` if the tag is not
+    /// selected. Inverted matchers rank every selected tag as 0.
+    pub fn rank(&self, tag: &str) -> Option<usize> {
+        let hit = match self.exact.get(tag) {
+            Some(&idx) => Some(idx),
+            None => self.regexes.iter().position(|re| re.is_match(tag)),
+        };
+        match (hit, self.invert) {
+            (Some(_), true) => None,
+            (None, true) => Some(0),
+            (hit, false) => hit,
+        }
+    }
+}
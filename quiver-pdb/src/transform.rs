@@ -0,0 +1,248 @@
+//! Per-record structural transforms for the `qvtransform` pipeline.
+//!
+//! Steps run in a fixed order: strip atoms, rename chains, renumber
+//! residues, recenter, rewrite serials. `TER`, `ANISOU` and `SIGUIJ` lines
+//! follow the atom before them.
+
+use crate::pdb::Atom;
+use crate::select::Selection;
+use crate::{QuiverCore, QuiverError, Result};
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(Debug, Clone, Default)]
+pub struct Transform {
+    /// Atoms to keep; see [`strip_selection`].
+    pub keep: Option<Selection>,
+    /// Chain renames; chains not in the map are left alone.
+    pub rechain: HashMap<char, char>,
+    /// Number residues from 1 in each chain and clear insertion codes.
+    pub renumber: bool,
+    /// Move the centroid of all atoms to the origin.
+    pub recenter: bool,
+    /// Number atoms from 1, remapping `CONECT` records.
+    pub reserial: bool,
+}
+
+/// The selection keeping everything but the stripped atom kinds, or `None`
+/// when nothing is stripped.
+pub fn strip_selection(hydrogens: bool, hetatm: bool, waters: bool) -> Result<Option<Selection>> {
+    let stripped: Vec<&str> = [(hydrogens, "hydrogen"), (hetatm, "hetatm"), (waters, "water")]
+        .into_iter()
+        .filter_map(|(strip, flag)| strip.then_some(flag))
+        .collect();
+    if stripped.is_empty() {
+        return Ok(None);
+    }
+    Selection::parse(&format!("not ({})", stripped.join(" or "))).map(Some)
+}
+
+fn is_atom(line: &str) -> bool {
+    line.starts_with("ATOM") || line.starts_with("HETATM")
+}
+
+/// Lines that describe the atom before them.
+fn follows_atom(line: &str) -> bool {
+    line.starts_with("ANISOU") || line.starts_with("SIGUIJ")
+}
+
+/// Overwrite columns `start..end` with `value`, padding short lines with
+/// spaces. Fails, naming `field`, when the value does not fit the columns.
+fn set_columns(line: &mut String, start: usize, end: usize, field: &str, value: &str) -> Result<()> {
+    if line.len() < end {
+        line.extend(std::iter::repeat_n(' ', end - line.len()));
+    }
+    if value.len() != end - start || !line.is_char_boundary(start) || !line.is_char_boundary(end) {
+        return Err(QuiverError::Invalid(format!(
+            "{} '{}' does not fit in PDB columns {}-{}",
+            field,
+            value.trim(),
+            start + 1,
+            end
+        )));
+    }
+    line.replace_range(start..end, value);
+    Ok(())
+}
+
+fn columns(line: &str, start: usize, end: usize) -> Option<&str> {
+    line.get(start..end.min(line.len()))
+}
+
+fn rechain(lines: &mut [String], map: &HashMap<char, char>) -> Result<()> {
+    for line in lines.iter_mut() {
+        if !(is_atom(line) || follows_atom(line) || line.starts_with("TER")) {
+            continue;
+        }
+        let chain = line.as_bytes().get(21).map(|&b| b as char);
+        if let Some(&new) = chain.and_then(|c| map.get(&c)) {
+            set_columns(line, 21, 22, "chain ID", &new.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn renumber(lines: &mut [String]) -> Result<()> {
+    let mut last: HashMap<char, ((i64, char), i64)> = HashMap::new();
+    // resName, chain, resSeq and iCode (columns 18-27) of the last atom.
+    let mut residue: Option<String> = None;
+    for line in lines.iter_mut() {
+        if is_atom(line) {
+            let Some(atom) = Atom::parse(line) else { continue };
+            let key = (atom.res_seq, atom.i_code);
+            let number = match last.get(&atom.chain) {
+                Some(&(prev, n)) if prev == key => n,
+                Some(&(_, n)) => n + 1,
+                None => 1,
+            };
+            last.insert(atom.chain, (key, number));
+            set_columns(line, 22, 27, "residue number", &format!("{:>4} ", number))?;
+            residue = columns(line, 17, 27).map(String::from);
+        } else if follows_atom(line) || (line.starts_with("TER") && line.trim_end().len() > 6) {
+            if let Some(residue) = &residue {
+                set_columns(line, 17, 27, "residue", residue)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn recenter(lines: &mut [String]) -> Result<()> {
+    let atoms: Vec<Atom> = lines.iter().filter_map(|line| Atom::parse(line)).collect();
+    if atoms.is_empty() {
+        return Ok(());
+    }
+    let n = atoms.len() as f64;
+    let center = [
+        atoms.iter().map(|a| a.x).sum::<f64>() / n,
+        atoms.iter().map(|a| a.y).sum::<f64>() / n,
+        atoms.iter().map(|a| a.z).sum::<f64>() / n,
+    ];
+    // Lines that do not parse are left as they are.
+    for line in lines.iter_mut() {
+        let Some(atom) = Atom::parse(line) else { continue };
+        let coordinates = [(30, "x", atom.x - center[0]), (38, "y", atom.y - center[1]), (46, "z", atom.z - center[2])];
+        for (start, axis, value) in coordinates {
+            set_columns(line, start, start + 8, &format!("{} coordinate", axis), &format!("{:8.3}", value))?;
+        }
+    }
+    Ok(())
+}
+
+/// Serial `n` as it fits in five columns: after 99999 numbering starts
+/// again at 1.
+fn wrap_serial(n: i64) -> i64 {
+    (n - 1) % 99_999 + 1
+}
+
+fn reserial(lines: &mut [String]) -> Result<()> {
+    let mut renumbered: HashMap<i64, i64> = HashMap::new();
+    let mut serial = 0;
+    for line in lines.iter_mut() {
+        if is_atom(line) {
+            serial += 1;
+            if let Some(old) = columns(line, 6, 11).and_then(|s| s.trim().parse().ok()) {
+                renumbered.insert(old, serial);
+            }
+            set_columns(line, 6, 11, "serial", &format!("{:>5}", wrap_serial(serial)))?;
+        } else if follows_atom(line) {
+            set_columns(line, 6, 11, "serial", &format!("{:>5}", wrap_serial(serial)))?;
+        } else if line.starts_with("TER") && line.trim_end().len() > 6 {
+            serial += 1;
+            set_columns(line, 6, 11, "serial", &format!("{:>5}", wrap_serial(serial)))?;
+        }
+    }
+    for line in lines.iter_mut().filter(|line| line.starts_with("CONECT")) {
+        let end = line.trim_end().len().min(31);
+        for start in (6..end).step_by(5) {
+            let new = columns(line, start, start + 5)
+                .and_then(|s| s.trim().parse().ok())
+                .and_then(|old| renumbered.get(&old));
+            if let Some(&new) = new {
+                set_columns(line, start, start + 5, "serial", &format!("{:>5}", wrap_serial(new)))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Transform {
+    /// Whether any step is enabled.
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_none() && self.rechain.is_empty() && !self.renumber && !self.recenter && !self.reserial
+    }
+
+    /// Apply the enabled steps to the PDB lines of one record. Fails when a
+    /// new value does not fit its PDB columns, such as a coordinate of
+    /// -1000 or below or a residue number above 9999.
+    pub fn apply(&self, lines: &[String]) -> Result<Vec<String>> {
+        let kept = match &self.keep {
+            Some(selection) => selection.filter_lines(lines),
+            None => lines.to_vec(),
+        };
+        let mut lines: Vec<String> =
+            kept.into_iter().map(|line| line.trim_end_matches(['\n', '\r']).to_string()).collect();
+        if !self.rechain.is_empty() {
+            rechain(&mut lines, &self.rechain)?;
+        }
+        if self.renumber {
+            renumber(&mut lines)?;
+        }
+        if self.recenter {
+            recenter(&mut lines)?;
+        }
+        if self.reserial {
+            reserial(&mut lines)?;
+        }
+        Ok(lines)
+    }
+}
+
+impl QuiverCore {
+    /// Write every record to `out` with `transform` applied. Records left
+    /// without atoms are skipped; see [`QuiverCore::rewrite_records`]. A
+    /// value that does not fit its columns fails with the record's tag.
+    pub fn transform<W: Write>(&self, transform: &Transform, out: &mut W) -> Result<(usize, Vec<String>)> {
+        self.rewrite_records(out, |record| {
+            transform.apply(&record.lines).map_err(|e| QuiverError::Invalid(format!("{}: {}", record.tag, e)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn atom(serial: usize, res_seq: usize, x: f64) -> String {
+        format!("ATOM  {:>5}  CA  ALA A{:>4}    {:8.3}   0.000   0.000  1.00 80.00           C", serial % 100_000, res_seq % 10_000, x)
+    }
+
+    #[test]
+    fn values_that_do_not_fit_their_columns_fail() {
+        let lines: Vec<String> = (0..10_000).map(|i| atom(i + 1, i % 9_000 + 1, 0.0)).collect();
+        let renumber = Transform { renumber: true, ..Default::default() };
+        let err = renumber.apply(&lines).unwrap_err();
+        assert_eq!(err.to_string(), "residue number '10000' does not fit in PDB columns 23-27");
+        assert!(renumber.apply(&lines[..9_999]).is_ok());
+
+        let lines = [atom(1, 1, -600.0), atom(2, 2, 900.0)];
+        let recenter = Transform { recenter: true, ..Default::default() };
+        assert!(recenter.apply(&lines).is_ok());
+        let lines = [atom(1, 1, -900.0), atom(2, 2, 1200.0)];
+        let err = recenter.apply(&lines).unwrap_err();
+        assert!(matches!(err, QuiverError::Invalid(_)));
+        assert_eq!(err.to_string(), "x coordinate '-1050.000' does not fit in PDB columns 31-38");
+
+        let rechain = Transform { rechain: HashMap::from([('A', 'é')]), ..Default::default() };
+        assert!(rechain.apply(&lines).unwrap_err().to_string().starts_with("chain ID 'é'"));
+    }
+
+    #[test]
+    fn serials_wrap_instead_of_overflowing() {
+        let lines: Vec<String> = (0..100_001).map(|i| atom(i + 1, 1, 0.0)).collect();
+        let reserial = Transform { reserial: true, ..Default::default() };
+        let lines = reserial.apply(&lines).unwrap();
+        assert_eq!(&lines[99_998][6..11], "99999");
+        assert_eq!(&lines[99_999][6..11], "    1");
+    }
+}
@@ -0,0 +1,257 @@
+//! Buffered, long-lived writer for adding many records to a Quiver file.
+
+use crate::checksum::ChecksumAlgo;
+use crate::header::QuiverHeader;
+use crate::score::parse_score_str;
+use crate::{lock_archive, QuiverCore, QuiverError, QuiverRecord, Result};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Join PDB lines into a record payload, adding missing newlines.
+pub(crate) fn lines_payload(lines: &[String]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for line in lines {
+        payload.extend_from_slice(line.as_bytes());
+        if !line.ends_with('\n') {
+            payload.push(b'\n');
+        }
+    }
+    payload
+}
+
+/// Turn PDB text into a record payload ending in a newline. With `normalize`,
+/// CRLF (and lone CR) line endings become LF and trailing `END` and blank
+/// lines are dropped. The input is borrowed when nothing has to change.
+pub fn record_payload(text: &[u8], normalize: bool) -> Cow<'_, [u8]> {
+    let mut payload = Cow::Borrowed(text);
+    if normalize {
+        if text.contains(&b'\r') {
+            let mut unix = Vec::with_capacity(text.len());
+            let mut bytes = text.iter().peekable();
+            while let Some(&b) = bytes.next() {
+                if b == b'\r' {
+                    bytes.next_if_eq(&&b'\n');
+                    unix.push(b'\n');
+                } else {
+                    unix.push(b);
+                }
+            }
+            payload = Cow::Owned(unix);
+        }
+        let end = content_end(&payload);
+        match &mut payload {
+            Cow::Borrowed(bytes) => *bytes = &bytes[..end],
+            Cow::Owned(bytes) => bytes.truncate(end),
+        }
+    }
+    if !payload.is_empty() && !payload.ends_with(b"\n") {
+        payload.to_mut().push(b'\n');
+    }
+    payload
+}
+
+/// Length of `text` without its trailing `END` and blank lines.
+fn content_end(text: &[u8]) -> usize {
+    let mut end = text.len();
+    while end > 0 {
+        let body = text[..end].strip_suffix(b"\n").unwrap_or(&text[..end]);
+        let start = body.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        let line = body[start..].trim_ascii();
+        if !line.is_empty() && line != b"END" {
+            break;
+        }
+        end = start;
+    }
+    end
+}
+
+/// Encode one record (`QV_TAG`, optional annotations and the payload) into
+/// `out`.
+pub(crate) fn encode_record(
+    out: &mut Vec<u8>,
+    tag: &str,
+    payload: &[u8],
+    score_str: Option<&str>,
+    meta_str: Option<&str>,
+    checksum: Option<ChecksumAlgo>,
+) {
+    out.extend_from_slice(format!("QV_TAG {}\n", tag).as_bytes());
+    if let Some(score) = score_str {
+        out.extend_from_slice(format!("QV_SCORE {} {}\n", tag, score).as_bytes());
+    }
+    if let Some(meta) = meta_str {
+        out.extend_from_slice(format!("QV_META {} {}\n", tag, meta).as_bytes());
+    }
+    if let Some(algo) = checksum {
+        out.extend_from_slice(algo.annotation(tag, payload).as_bytes());
+        out.push(b'\n');
+    }
+    out.extend_from_slice(payload);
+}
+
+/// Appends records through one buffered handle instead of reopening the file
+/// for every structure, as `QuiverCore::add_pdb` does.
+#[derive(Debug)]
+pub struct QuiverWriter {
+    fnm: String,
+    out: Option<BufWriter<File>>,
+    tags: HashSet<String>,
+    header: Option<QuiverHeader>,
+    checksum: Option<ChecksumAlgo>,
+    fsync: bool,
+    buf: Vec<u8>,
+    /// The archive's lock file, held until the writer is closed.
+    lock: Option<File>,
+}
+
+impl QuiverWriter {
+    /// Open `filename` for appending. With `lock`, the archive's exclusive
+    /// advisory lock (on `<filename>.lock`) is held until the writer is closed. With `header`, a new or empty file
+    /// gets a header with these extra entries, written before the first
+    /// record.
+    pub fn open(
+        filename: &str,
+        header: Option<&[(String, String)]>,
+        checksum: Option<ChecksumAlgo>,
+        fsync: bool,
+        lock: bool,
+    ) -> Result<Self> {
+        let lock = if lock { Some(lock_archive(filename)?) } else { None };
+        let file = OpenOptions::new().create(true).append(true).open(filename)?;
+        // Read existing tags only once the lock (if any) is held.
+        let (tags, _) = QuiverCore::read_tags(filename)?;
+        let is_empty = file.metadata()?.len() == 0;
+        Ok(QuiverWriter {
+            fnm: filename.to_string(),
+            out: Some(BufWriter::new(file)),
+            tags: tags.into_iter().collect(),
+            header: header.filter(|_| is_empty).map(QuiverHeader::for_new_file),
+            checksum,
+            fsync,
+            buf: Vec::new(),
+            lock,
+        })
+    }
+
+    /// Append a record; `payload` should come from `record_payload`.
+    pub fn add(&mut self, tag: &str, payload: &[u8], score_str: Option<&str>, meta_str: Option<&str>) -> Result<()> {
+        let Some(out) = self.out.as_mut() else {
+            return Err(QuiverError::Mode(format!("QuiverWriter for {} is closed.", self.fnm)));
+        };
+        if self.tags.contains(tag) {
+            return Err(QuiverError::DuplicateTag(tag.to_string()));
+        }
+        if let Some(header) = self.header.take() {
+            header.write(out)?;
+        }
+        self.buf.clear();
+        encode_record(&mut self.buf, tag, payload, score_str, meta_str, self.checksum);
+        out.write_all(&self.buf)?;
+        self.tags.insert(tag.to_string());
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        match self.out.as_mut() {
+            Some(out) => Ok(out.flush()?),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush, optionally fsync, and release the file (and its lock). Closing
+    /// twice is a no-op.
+    pub fn close(&mut self) -> Result<()> {
+        let Some(out) = self.out.take() else { return Ok(()) };
+        let file = out.into_inner().map_err(|e| e.into_error())?;
+        if self.fsync {
+            file.sync_all()?;
+        }
+        self.lock = None;
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.out.is_none()
+    }
+}
+
+/// Whether any line is an ATOM or HETATM record.
+fn has_atoms(lines: &[String]) -> bool {
+    lines.iter().any(|line| line.starts_with("ATOM") || line.starts_with("HETATM"))
+}
+
+impl QuiverRecord {
+    /// The algorithm named in the record's `QV_CHECKSUM` line, if any.
+    pub fn checksum_algo(&self) -> Option<ChecksumAlgo> {
+        let checksum = self.checksum.as_deref()?;
+        parse_score_str(checksum).into_iter().find_map(|(key, _)| ChecksumAlgo::from_name(&key).ok())
+    }
+}
+
+impl QuiverCore {
+    /// Write every record to `out` with its PDB lines replaced by
+    /// `rewrite(record)`, stopping at the first error it returns. Scores and
+    /// metadata are kept and checksums recomputed. Records left without atoms
+    /// are skipped; their tags are returned with the number of records
+    /// written.
+    pub fn rewrite_records<W, F>(&self, out: &mut W, mut rewrite: F) -> Result<(usize, Vec<String>)>
+    where
+        W: Write,
+        F: FnMut(&QuiverRecord) -> Result<Vec<String>>,
+    {
+        self.write_header(out)?;
+        let mut written = 0;
+        let mut emptied = Vec::new();
+        let mut buf = Vec::new();
+        for record in self.records()? {
+            let record = record?;
+            let lines = rewrite(&record)?;
+            if !has_atoms(&lines) {
+                emptied.push(record.tag);
+                continue;
+            }
+            buf.clear();
+            encode_record(
+                &mut buf,
+                &record.tag,
+                &lines_payload(&lines),
+                record.score.as_deref(),
+                record.meta.as_deref(),
+                record.checksum_algo(),
+            );
+            out.write_all(&buf)?;
+            written += 1;
+        }
+        Ok((written, emptied))
+    }
+}
+
+/// Write PDB files as Quiver records to `out`, tagged by file stem, after a
+/// `QV_HEADER` block when `header` is set. The PDB text is copied unchanged
+/// apart from a missing final newline. Returns the number of records written.
+pub fn write_pdb_files<W: Write>(
+    pdb_files: &[String],
+    checksum: Option<ChecksumAlgo>,
+    header: bool,
+    out: &mut W,
+) -> Result<usize> {
+    if header {
+        QuiverHeader::for_new_file(&[]).write(out)?;
+    }
+    for pdbfn in pdb_files {
+        let tag = Path::new(pdbfn).file_stem().and_then(|s| s.to_str()).unwrap_or("UNKNOWN");
+        let mut file = File::open(pdbfn).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", pdbfn, e)))?;
+        let mut text = Vec::new();
+        file.read_to_end(&mut text)?;
+        let payload = record_payload(&text, false);
+        writeln!(out, "QV_TAG {}", tag)?;
+        if let Some(algo) = checksum {
+            writeln!(out, "{}", algo.annotation(tag, &payload))?;
+        }
+        out.write_all(&payload)?;
+    }
+    Ok(pdb_files.len())
+}
@@ -0,0 +1,269 @@
+//! End-to-end tests of the `quiver` binary (built with the `cli` feature).
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+const D1: &str = "ATOM      1  CA  ALA A   1       1.000   2.000   3.000  1.00 10.00           C\n";
+const D2: &str = "ATOM      1  CA  GLY A   1       4.000   5.000   6.000  1.00 20.00           C\n";
+const D3: &str = "ATOM      1  CA  SER B   1       7.000   8.000   9.000  1.00 30.00           C\n";
+
+/// A scratch directory holding `scores.qv`, removed again when dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("quiver_cli_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let scratch = Scratch(dir);
+        scratch.write(
+            "scores.qv",
+            &format!(
+                "QV_TAG d1\nQV_SCORE d1 ddg=-12.5|plddt=90\n{}\
+                 QV_TAG d2\nQV_SCORE d2 ddg=-3|plddt=70\n{}\
+                 QV_TAG d3\nQV_SCORE d3 ddg=-8|plddt=85\n{}",
+                D1, D2, D3
+            ),
+        );
+        scratch
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+
+    fn write(&self, name: &str, content: &str) {
+        fs::write(self.path(name), content).unwrap();
+    }
+
+    fn read(&self, name: &str) -> String {
+        fs::read_to_string(self.path(name)).unwrap()
+    }
+
+    /// Run `quiver` in the scratch directory, feeding `stdin` to it.
+    fn run(&self, args: &[&str], stdin: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_quiver"))
+            .args(args)
+            .current_dir(&self.0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    /// Run `quiver` and return its stdout, failing on a non-zero exit.
+    fn ok(&self, args: &[&str], stdin: &str) -> String {
+        let output = self.run(args, stdin);
+        assert!(output.status.success(), "quiver {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn tags(&self, quiver_file: &str) -> Vec<String> {
+        self.ok(&["ls", quiver_file], "").lines().map(String::from).collect()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn ls_lists_tags_in_file_order() {
+    let dir = Scratch::new("ls");
+    assert_eq!(dir.ok(&["ls", "scores.qv"], ""), "d1\nd2\nd3\n");
+}
+
+#[test]
+fn frompdbs_tags_records_by_file_name() {
+    let dir = Scratch::new("frompdbs");
+    dir.write("first.pdb", D1);
+    dir.write("second.pdb", D2.trim_end());
+
+    let qv = dir.ok(&["frompdbs", "first.pdb", "second.pdb"], "");
+    assert_eq!(qv, format!("QV_TAG first\n{}QV_TAG second\n{}", D1, D2));
+
+    dir.ok(&["frompdbs", "first.pdb", "--checksum", "crc32", "--header", "--output", "out.qv"], "");
+    let qv = dir.read("out.qv");
+    assert!(qv.starts_with("QV_HEADER format_version="));
+    assert!(qv.contains("QV_TAG first\nQV_CHECKSUM first crc32="));
+    assert!(qv.ends_with(D1));
+
+    let output = dir.run(&["frompdbs", "first.pdb", "--output", "first.pdb"], "");
+    assert!(!output.status.success());
+    assert_eq!(dir.read("first.pdb"), D1);
+}
+
+#[test]
+fn extract_writes_every_format() {
+    let dir = Scratch::new("extract");
+    dir.ok(&["extract", "scores.qv", "--output", "pdbs"], "");
+    assert_eq!(dir.read("pdbs/d1.pdb"), D1);
+    assert_eq!(dir.read("pdbs/d3.pdb"), D3);
+
+    let output = dir.run(&["extract", "scores.qv", "--output", "chain_a", "--select", "chain A"], "");
+    assert!(stderr(&output).contains("No atoms selected in d3, skipping"));
+    assert!(dir.path("chain_a/d2.pdb").exists());
+    assert!(!dir.path("chain_a/d3.pdb").exists());
+
+    dir.ok(&["extract", "scores.qv", "--format", "multimodel", "--output", "models.pdb"], "");
+    let models = dir.read("models.pdb");
+    assert!(models.starts_with("REMARK   1 MODEL    1 QV_TAG d1\n"));
+    assert_eq!(models.matches("ENDMDL").count(), 3);
+
+    dir.ok(&["extract", "scores.qv", "--format", "cif", "--output", "cif"], "");
+    assert!(dir.read("cif/d2.cif").starts_with("data_d2"));
+
+    dir.ok(&["extract", "scores.qv", "--format", "tar", "--output", "pdbs.tar"], "");
+    assert!(dir.path("pdbs.tar").metadata().unwrap().len() > 0);
+
+    dir.ok(&["extract", "scores.qv", "--format", "parquet", "--output", "atoms.parquet"], "");
+    assert!(fs::read(dir.path("atoms.parquet")).unwrap().starts_with(b"PAR1"));
+
+    let output = dir.run(&["extract", "scores.qv", "--format", "tar"], "");
+    assert!(stderr(&output).contains("--output is required"));
+}
+
+#[test]
+fn slice_takes_tags_from_arguments_or_stdin() {
+    let dir = Scratch::new("slice");
+    let out = dir.ok(&["slice", "scores.qv", "d3", "d1"], "");
+    assert_eq!(out, format!("QV_TAG d1\nQV_SCORE d1 ddg=-12.5|plddt=90\n{}QV_TAG d3\nQV_SCORE d3 ddg=-8|plddt=85\n{}", D1, D3));
+
+    dir.ok(&["slice", "scores.qv", "--ordered", "--output", "ordered.qv"], "d3\nd1\n");
+    assert_eq!(dir.tags("ordered.qv"), ["d3", "d1"]);
+
+    dir.ok(&["slice", "scores.qv", "d*", "--match", "glob", "--invert", "--output", "none.qv"], "");
+    assert_eq!(dir.read("none.qv"), "");
+
+    let output = dir.run(&["slice", "scores.qv", "d2", "missing"], "");
+    assert!(output.status.success());
+    assert!(stderr(&output).contains("Tag not found in Quiver file: missing"));
+
+    let output = dir.run(&["slice", "scores.qv"], "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("No tags provided"));
+}
+
+#[test]
+fn split_writes_numbered_parts() {
+    let dir = Scratch::new("split");
+    dir.ok(&["split", "scores.qv", "2", "--prefix", "part", "--output-dir", "parts"], "");
+    assert_eq!(dir.tags("parts/part_0.qv"), ["d1", "d2"]);
+    assert_eq!(dir.tags("parts/part_1.qv"), ["d3"]);
+
+    let output = dir.run(&["split", "scores.qv", "0"], "");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn rename_rewrites_tags_in_place() {
+    let dir = Scratch::new("rename");
+    dir.ok(&["rename", "scores.qv"], "x1 x2\nx3\n");
+    assert_eq!(dir.tags("scores.qv"), ["x1", "x2", "x3"]);
+    assert!(dir.read("scores.qv").contains("QV_SCORE x2 ddg=-3|plddt=70\n"));
+
+    dir.ok(&["rename", "scores.qv", "--prefix", "run_"], "");
+    assert_eq!(dir.tags("scores.qv"), ["run_x1", "run_x2", "run_x3"]);
+
+    dir.ok(&["rename", "scores.qv", "--regex", "x(\\d)", "--replace", "d$1"], "");
+    assert_eq!(dir.tags("scores.qv"), ["run_d1", "run_d2", "run_d3"]);
+
+    dir.write("map.txt", "run_d2 middle\n");
+    dir.ok(&["rename", "scores.qv", "--map", "map.txt"], "");
+    assert_eq!(dir.tags("scores.qv"), ["run_d1", "middle", "run_d3"]);
+
+    let output = dir.run(&["rename", "scores.qv", "same", "same", "same"], "");
+    assert!(!output.status.success());
+    assert_eq!(dir.tags("scores.qv"), ["run_d1", "middle", "run_d3"]);
+}
+
+#[test]
+fn scorefile_writes_a_table_next_to_the_archive() {
+    let dir = Scratch::new("scorefile");
+    dir.ok(&["scorefile", "scores.qv"], "");
+    assert_eq!(dir.read("scores.sc"), "tag\tddg\tplddt\nd1\t-12.5\t90\nd2\t-3\t70\nd3\t-8\t85\n");
+
+    dir.ok(&["scorefile", "scores.qv", "--output", "table.csv"], "");
+    assert!(dir.read("table.csv").starts_with("tag,ddg,plddt\n"));
+
+    let output = dir.run(&["scorefile", "scores.qv", "--output", "scores.qv"], "");
+    assert!(!output.status.success());
+    assert_eq!(dir.tags("scores.qv"), ["d1", "d2", "d3"]);
+}
+
+#[test]
+fn cat_merges_archives_with_unique_tags() {
+    let dir = Scratch::new("cat");
+    dir.ok(&["slice", "scores.qv", "d1", "--output", "a.qv"], "");
+    dir.ok(&["slice", "scores.qv", "d2", "d3", "--output", "b.qv"], "");
+    dir.ok(&["cat", "a.qv", "b.qv", "--output", "merged.qv"], "");
+    assert_eq!(dir.read("merged.qv"), dir.read("scores.qv"));
+
+    let output = dir.run(&["cat", "a.qv", "scores.qv"], "");
+    assert!(!output.status.success());
+
+    let output = dir.run(&["cat", "a.qv", "b.qv", "--output", "./b.qv"], "");
+    assert!(stderr(&output).contains("must not be one of the inputs"));
+    assert_eq!(dir.tags("b.qv"), ["d2", "d3"]);
+}
+
+#[test]
+fn filter_keeps_records_meeting_every_condition() {
+    let dir = Scratch::new("filter");
+    let out = dir.ok(&["filter", "scores.qv", "ddg<-5"], "");
+    assert_eq!(out.matches("QV_TAG").count(), 2);
+    dir.ok(&["filter", "scores.qv", "ddg<-5", "plddt>=90", "--output", "best.qv"], "");
+    assert_eq!(dir.tags("best.qv"), ["d1"]);
+
+    let output = dir.run(&["filter", "scores.qv", "ddg"], "");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn output_must_not_overwrite_the_input() {
+    let dir = Scratch::new("same_output");
+    let before = dir.read("scores.qv");
+    for args in [
+        &["slice", "scores.qv", "d1", "--output", "scores.qv"][..],
+        &["filter", "scores.qv", "ddg<0", "--output", "./scores.qv"],
+        &["extract", "scores.qv", "--format", "tar", "--output", "scores.qv"],
+    ] {
+        let output = dir.run(args, "");
+        assert_eq!(output.status.code(), Some(1), "quiver {:?}", args);
+        assert!(stderr(&output).contains("must not be one of the inputs"));
+    }
+    assert_eq!(dir.read("scores.qv"), before);
+}
+
+#[test]
+fn check_exits_with_failure_on_a_damaged_record() {
+    let dir = Scratch::new("check");
+    dir.write("first.pdb", D1);
+    dir.write("second.pdb", D2);
+    dir.ok(&["frompdbs", "first.pdb", "second.pdb", "--checksum", "xxh64", "--output", "sums.qv"], "");
+    let output = dir.run(&["check", "sums.qv"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr(&output).contains("Verified 2 records"));
+
+    dir.write("sums.qv", &dir.read("sums.qv").replace("5.000", "5.001"));
+    let output = dir.run(&["check", "sums.qv"], "");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("second\t"), "{}", stdout);
+    assert_eq!(stdout.lines().count(), 1);
+
+    let output = dir.run(&["check", "scores.qv"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stderr(&output).contains("3 records have no checksum"));
+}
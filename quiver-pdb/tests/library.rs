@@ -0,0 +1,118 @@
+//! Round trips through the public library API.
+
+use quiver_pdb::{
+    encode_score, parse_score_str, record_payload, ChecksumAlgo, QuiverCore, QuiverError, QuiverWriter, VerifyStatus,
+};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+const PDB_1: &str = "ATOM      1  N   ALA A   1      27.526  24.362   4.697  1.00 20.00           N\n\
+                     ATOM      2  CA  ALA A   1      28.100  25.000   5.000  1.00 20.00           C\n";
+const PDB_2: &str = "ATOM      1  CA  GLY B   7       1.000   2.000   3.000  1.00 30.00           C\r\nEND\r\n";
+
+/// A path in the temp directory, removed (with its lock file) when dropped.
+struct TempQv(PathBuf);
+
+impl TempQv {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("quiver_lib_{}_{}.qv", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        TempQv(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+
+    fn open(&self, mode: &str) -> QuiverCore {
+        QuiverCore::new(self.path().to_string(), mode.to_string()).unwrap()
+    }
+}
+
+impl Drop for TempQv {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+        let _ = fs::remove_file(format!("{}.lock", self.path()));
+    }
+}
+
+#[test]
+fn writer_records_read_back_unchanged() {
+    let qv = TempQv::new("round_trip");
+    let score = encode_score(&[("ddg", "-12.5"), ("note", "two words|x=y")]);
+    let mut writer = QuiverWriter::open(qv.path(), None, Some(ChecksumAlgo::Crc32), false, true).unwrap();
+    writer.add("first", &record_payload(PDB_1.as_bytes(), true), Some(&score), Some("source=a.pdb")).unwrap();
+    writer.add("second", &record_payload(PDB_2.as_bytes(), true), None, None).unwrap();
+    writer.close().unwrap();
+    writer.close().unwrap();
+    assert!(writer.is_closed());
+
+    let core = qv.open("r");
+    assert_eq!(core.get_tags(), ["first", "second"]);
+    assert_eq!(core.get_pdb_bytes("first").unwrap().as_ref(), PDB_1.as_bytes());
+    assert_eq!(
+        core.get_pdblines("second").unwrap(),
+        ["ATOM      1  CA  GLY B   7       1.000   2.000   3.000  1.00 30.00           C\n"]
+    );
+
+    let first = core.get_record("first").unwrap();
+    assert_eq!(parse_score_str(first.score.as_deref().unwrap())[1].1, "two words|x=y");
+    assert_eq!(first.meta.as_deref(), Some("source=a.pdb"));
+    assert_eq!(first.checksum_algo(), Some(ChecksumAlgo::Crc32));
+    assert_eq!(core.get_record("second").unwrap().score, None);
+
+    let records: Vec<_> = core.records().unwrap().map(|record| record.unwrap().tag).collect();
+    assert_eq!(records, ["first", "second"]);
+    let statuses: Vec<_> = core.verify().unwrap().into_iter().map(|result| result.status).collect();
+    assert_eq!(statuses, [VerifyStatus::Ok, VerifyStatus::Ok]);
+}
+
+#[test]
+fn appends_through_core_and_writer_share_one_file() {
+    let qv = TempQv::new("append");
+    let mut core = qv.open("w");
+    core.enable_header();
+    core.set_metadata("project", "demo").unwrap();
+    core.add_pdb(PDB_1.as_bytes(), "a", Some("ddg=1"), None, None).unwrap();
+    assert!(matches!(core.set_metadata("late", "x"), Err(QuiverError::Mode(_))));
+
+    let mut writer = QuiverWriter::open(qv.path(), Some(&[]), None, false, false).unwrap();
+    writer.add("b", PDB_1.as_bytes(), None, None).unwrap();
+    assert!(matches!(writer.add("a", PDB_1.as_bytes(), None, None), Err(QuiverError::DuplicateTag(tag)) if tag == "a"));
+    writer.close().unwrap();
+
+    // The core picks up the writer's record before its next append.
+    assert!(matches!(core.add_pdb(PDB_1.as_bytes(), "b", None, None, None), Err(QuiverError::DuplicateTag(_))));
+    core.add_pdb(PDB_1.as_bytes(), "c", None, None, Some(ChecksumAlgo::Xxh64)).unwrap();
+
+    let text = fs::read_to_string(qv.path()).unwrap();
+    assert_eq!(text.matches("QV_HEADER format_version=").count(), 1);
+    let reader = qv.open("r");
+    assert_eq!(reader.get_tags(), ["a", "b", "c"]);
+    assert_eq!(reader.header().and_then(|header| header.get("project")), Some("demo"));
+    let statuses: Vec<_> = reader.verify().unwrap().into_iter().map(|result| result.status).collect();
+    assert_eq!(statuses, [VerifyStatus::Unchecked, VerifyStatus::Unchecked, VerifyStatus::Ok]);
+}
+
+#[test]
+fn errors_name_their_cause() {
+    let qv = TempQv::new("errors");
+    assert!(matches!(QuiverCore::new(qv.path().to_string(), "a".to_string()), Err(QuiverError::Mode(_))));
+
+    let mut core = qv.open("w");
+    core.add_pdb(PDB_1.as_bytes(), "a", None, None, None).unwrap();
+    let err = core.get_pdblines("a").unwrap_err();
+    assert!(matches!(err, QuiverError::Mode(_)));
+    assert_eq!(err.to_string(), "Quiver file must be opened in read mode to allow for reading.");
+
+    let mut reader = qv.open("r");
+    assert!(matches!(reader.add_pdb(PDB_1.as_bytes(), "b", None, None, None), Err(QuiverError::Mode(_))));
+    let err = reader.get_pdb_bytes("missing").unwrap_err();
+    assert!(matches!(err, QuiverError::TagNotFound(ref tag) if tag == "missing"));
+    assert_eq!(err.to_string(), "Requested tag: missing does not exist");
+
+    let err = QuiverWriter::open("/nonexistent/dir/x.qv", None, None, false, false).unwrap_err();
+    assert!(matches!(err, QuiverError::Io(_)));
+    assert!(err.source().is_some());
+}
@@ -1,15 +1,37 @@
+import math
 import os
 import pytest
 import time
 from pathlib import Path
 from quiver_pdb import (
     extract_pdbs,
+    extract_multimodel_pdb,
+    extract_mmcif,
+    extract_tar,
+    to_arrow,
     qvfrompdbs,
     extract_scorefile,
+    qvstats,
+    qvdssp,
+    qvinterface,
+    rmsd_to_reference,
+    rmsd_matrix,
+    qvcluster,
+    qvselect,
+    qvtransform,
     list_tags,
     rename_tags,
     qvslice,
     qvsplit,
+    qvcat,
+    qvverify,
+    qvrepair,
+    qvsample,
+    qvsort,
+    qvdedup,
+    qvdiff,
+    Quiver,
+    QuiverWriter,
 )
 
 # 테스트 데이터 디렉토리 설정
@@ -67,6 +89,14 @@ def test_qvfrompdbs():
     assert len(result) > 0
     assert end_time - start_time < 1.0  # 1초 이내 실행
 
+    # 마지막 줄바꿈이 없는 PDB 파일
+    unterminated = TEST_DATA_DIR / "unterminated.pdb"
+    unterminated.write_text("ATOM      1  N   ALA A   1      27.526  24.362   4.697  1.00 20.00")
+    for checksum in (None, "crc32"):
+        content = qvfrompdbs([str(unterminated), str(TEST_PDB_FILES[0])], checksum=checksum)
+        assert "20.00\nQV_TAG test_0\n" in content
+    unterminated.unlink()
+
 def test_extract_pdbs():
     """extract_pdbs 도구 테스트"""
     start_time = time.time()
@@ -78,6 +108,43 @@ def test_extract_pdbs():
     assert len(extracted_files) > 0
     assert end_time - start_time < 2.0  # 2초 이내 실행
 
+def test_extract_multimodel_pdb():
+    """extract_multimodel_pdb 도구 테스트"""
+    output = TEST_DATA_DIR / "models.pdb"
+    extract_multimodel_pdb(str(TEST_QV_FILE), str(output))
+
+    content = output.read_text()
+    lines = content.splitlines()
+    assert sum(l.startswith("MODEL ") for l in lines) == 3
+    assert content.count("ENDMDL") == 3
+    # 태그 REMARK는 첫 MODEL 앞에 모여 있다
+    first_model = next(i for i, l in enumerate(lines) if l.startswith("MODEL "))
+    assert lines[:first_model] == [f"REMARK   1 MODEL {i + 1:>4} QV_TAG test_{i}" for i in range(3)]
+
+def test_extract_mmcif():
+    """extract_mmcif 도구 테스트"""
+    output_dir = TEST_DATA_DIR / "cif"
+    extract_mmcif(str(TEST_QV_FILE), str(output_dir))
+
+    cif_files = sorted(output_dir.glob("*.cif"))
+    assert len(cif_files) == 3
+    content = cif_files[0].read_text()
+    assert content.startswith("data_test_0")
+    assert "ATOM 1 N N . ALA A 1 . 27.526 24.362 4.697" in content
+    for cif_file in cif_files:
+        cif_file.unlink()
+    output_dir.rmdir()
+
+def test_extract_tar():
+    """extract_tar 도구 테스트"""
+    import tarfile
+
+    output = TEST_DATA_DIR / "designs.tar.gz"
+    extract_tar(str(TEST_QV_FILE), str(output))
+
+    with tarfile.open(output, "r:gz") as tar:
+        assert tar.getnames() == ["test_0.pdb", "test_1.pdb", "test_2.pdb"]
+
 def test_extract_scorefile():
     """extract_scorefile 도구 테스트"""
     start_time = time.time()
@@ -88,6 +155,614 @@ def test_extract_scorefile():
     assert score_file.exists()
     assert end_time - start_time < 1.0  # 1초 이내 실행
 
+def test_extract_scorefile_formats():
+    """extract_scorefile 출력 형식 및 테이블 반환 테스트"""
+    import csv
+    import json
+
+    table = extract_scorefile(str(TEST_QV_FILE), return_table=True)
+    assert list(table) == ["tag", "rms", "score"]
+    assert table["tag"] == ["test_0", "test_1", "test_2"]
+    assert table["rms"] == [1.5, 1.5, 1.5]
+
+    csv_file = TEST_DATA_DIR / "scores.csv"
+    extract_scorefile(str(TEST_QV_FILE), output=str(csv_file))
+    rows = list(csv.DictReader(csv_file.open()))
+    assert rows[0] == {"tag": "test_0", "rms": "1.5", "score": "0.8"}
+
+    json_file = TEST_DATA_DIR / "scores.jsonl"
+    extract_scorefile(str(TEST_QV_FILE), output=str(json_file))
+    first = json.loads(json_file.read_text().splitlines()[0])
+    assert first == {"tag": "test_0", "rms": 1.5, "score": 0.8}
+
+    parquet_file = TEST_DATA_DIR / "scores.parquet"
+    extract_scorefile(str(TEST_QV_FILE), output=str(parquet_file))
+    assert parquet_file.read_bytes()[:4] == b"PAR1"
+
+    # "tag"라는 점수 항목은 태그 열을 덮어쓰지 않고 이름을 바꿔 내보냄
+    tagged = TEST_DATA_DIR / "tag_term.qv"
+    qv = Quiver(str(tagged), "w")
+    qv.add_pdb(TEST_PDB_FILES[0].read_text().splitlines(), "design_1", {"tag": "x", "score_tag": "y", "ddg": -1.0})
+    table = extract_scorefile(str(tagged), return_table=True)
+    assert list(table) == ["tag", "score_tag", "score_score_tag", "ddg"]
+    assert table["tag"] == ["design_1"]
+    assert table["score_tag"] == ["x"]
+    assert table["score_score_tag"] == ["y"]
+
+def test_add_pdb_score_dict():
+    """add_pdb 점수/메타데이터 dict 인코딩 테스트"""
+    qv_file = TEST_DATA_DIR / "scored.qv"
+    pdb_lines = TEST_PDB_FILES[0].read_text().splitlines()
+
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(
+        pdb_lines,
+        "design_1",
+        {"ddg": -12.5, "note": "kept | manual review", "seq": "ACDE"},
+        metadata={"model": "rfdiffusion v1.1", "created": "2026-01-01T00:00:00"},
+    )
+
+    content = qv_file.read_text()
+    assert "QV_SCORE design_1 ddg=-12.5|note=kept%20%7C%20manual%20review|seq=ACDE" in content
+    assert content.count("QV_META design_1 ") == 1
+
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_scores("design_1") == {"ddg": -12.5, "note": "kept | manual review", "seq": "ACDE"}
+    assert qv.get_metadata("design_1")["model"] == "rfdiffusion v1.1"
+    assert not any(l.startswith("QV_") for l in qv.get_pdblines("design_1"))
+
+    table = extract_scorefile(str(qv_file), return_table=True)
+    assert table["note"] == ["kept | manual review"]
+
+    # 탭/줄바꿈이 들어간 값은 TSV에서 따옴표로 감싼다
+    import csv
+    Quiver(str(qv_file), "w").add_pdb(pdb_lines, "design_2", {"ddg": -3.0, "note": "two\tcols\nrows"})
+    tsv_file = TEST_DATA_DIR / "scored.tsv"
+    extract_scorefile(str(qv_file), output=str(tsv_file))
+    rows = list(csv.DictReader(tsv_file.open(newline=""), delimiter="\t"))
+    assert [row["note"] for row in rows] == ["kept | manual review", "two\tcols\nrows"]
+
+def test_add_pdb_inputs():
+    """add_pdb 입력 형식(str/bytes/경로/줄 목록) 및 정규화 테스트"""
+    qv_file = TEST_DATA_DIR / "inputs.qv"
+    pdb_path = TEST_PDB_FILES[0]
+    text = pdb_path.read_text()
+    expected = text.splitlines(keepends=True)
+
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(text.splitlines(), "lines")
+    qv.add_pdb(text, "text")
+    qv.add_pdb(text.encode(), "bytes")
+    qv.add_pdb(pdb_path, "pathlike")
+    qv.add_pdb(str(pdb_path), "path")
+    qv.add_pdb(text.replace("\n", "\r\n") + "END\r\n\r\n", "crlf", normalize=True)
+    with pytest.raises(TypeError):
+        qv.add_pdb(42, "bad")
+
+    qv = Quiver(str(qv_file), "r")
+    for tag in ["lines", "text", "bytes", "pathlike", "path", "crlf"]:
+        assert qv.get_pdblines(tag) == expected
+
+    with QuiverWriter(str(qv_file)) as writer:
+        writer.add("raw", text.rstrip("\n"))
+        writer.add_many([("many", text.encode() + b"END\n", None)], normalize=True)
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_pdblines("raw") == expected
+    assert qv.get_pdblines("many") == expected
+
+def test_get_pdblines_formats():
+    """메모리 매핑 기반 bytes/memoryview 반환 테스트"""
+    qv = Quiver(str(TEST_QV_FILE), "r")
+    lines = qv.get_pdblines("test_1")
+    data = qv.get_pdblines("test_1", format="bytes")
+    view = qv.get_pdblines("test_1", format="memoryview")
+
+    assert isinstance(data, bytes)
+    assert data == "".join(lines).encode()
+    assert isinstance(view, memoryview)
+    assert view.readonly
+    assert view.tobytes() == data
+    assert bytes(view[:4]) == b"ATOM"
+    del qv
+    assert view.tobytes() == data  # 뷰가 매핑을 유지함
+
+    with pytest.raises(ValueError):
+        Quiver(str(TEST_QV_FILE), "r").get_pdblines("test_1", format="text")
+    with pytest.raises(IOError):
+        Quiver(str(TEST_QV_FILE), "r").get_pdblines("missing", format="memoryview")
+
+def test_quiver_header():
+    """QV_HEADER 생성 및 split/slice/merge/rename 보존 테스트"""
+    qv_file = TEST_DATA_DIR / "header.qv"
+    pdb_lines = TEST_PDB_FILES[0].read_text().splitlines()
+    qv = Quiver(str(qv_file), "w", metadata={"project": "binders"})
+    qv.add_pdb(pdb_lines, "h1")
+    qv.add_pdb(pdb_lines, "h2")
+
+    metadata = Quiver(str(qv_file), "r").metadata
+    assert metadata["format_version"] == "1"
+    assert metadata["tool"] == "quiver-pdb"
+    assert metadata["project"] == "binders"
+    assert "created" in metadata
+    assert Quiver(str(qv_file), "r").get_pdblines("h1") == [l + "\n" for l in pdb_lines]
+
+    # 헤더가 없는 기존 파일도 그대로 읽힘
+    assert Quiver(str(TEST_QV_FILE), "r").metadata == {}
+
+    # 헤더는 요청할 때만 쓰임
+    plain = TEST_DATA_DIR / "no_header.qv"
+    Quiver(str(plain), "w").add_pdb(pdb_lines, "p1")
+    with QuiverWriter(str(plain)) as writer:
+        writer.add("p2", pdb_lines)
+    assert "QV_HEADER" not in plain.read_text()
+    Quiver(str(plain), "w", header=True).add_pdb(pdb_lines, "p3")
+    assert "QV_HEADER" not in plain.read_text()
+    plain.unlink()
+    with QuiverWriter(str(plain), header=True) as writer:
+        writer.add("p1", pdb_lines)
+    assert Quiver(str(plain), "r").metadata["format_version"] == "1"
+    content = qvfrompdbs([str(TEST_PDB_FILES[0])], header=True)
+    assert content.startswith("QV_HEADER format_version=1\n")
+    assert "QV_HEADER" not in qvfrompdbs([str(TEST_PDB_FILES[0])])
+
+    sliced = TEST_DATA_DIR / "header_slice.qv"
+    qvslice(str(qv_file), ["h2"], output=str(sliced))
+    assert Quiver(str(sliced), "r").metadata == metadata
+
+    split_dir = TEST_DATA_DIR / "header_split"
+    qvsplit(str(qv_file), 1, "part", str(split_dir))
+    for part in sorted(split_dir.glob("*.qv")):
+        assert Quiver(str(part), "r").metadata == metadata
+        part.unlink()
+    split_dir.rmdir()
+
+    merged = TEST_DATA_DIR / "header_merged.qv"
+    rename_tags(str(sliced), prefix="copy_")
+    assert Quiver(str(sliced), "r").metadata == metadata
+    qvcat([str(qv_file), str(sliced)], str(merged))
+    merged_qv = Quiver(str(merged), "r")
+    assert merged_qv.get_tags() == ["h1", "h2", "copy_h2"]
+    assert merged_qv.metadata == metadata
+
+def test_qvverify():
+    """체크섬 기록 및 qvverify 손상 탐지 테스트"""
+    qv_file = TEST_DATA_DIR / "checked.qv"
+    qv_file.write_text(qvfrompdbs([str(pdb) for pdb in TEST_PDB_FILES], checksum="crc32"))
+    assert qvverify(str(qv_file)) == []
+
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(["ATOM      1  CA  GLY B   1       1.000   2.000   3.000  1.00 50.00"], "extra", checksum="xxh64")
+    assert qvverify(str(qv_file)) == []
+
+    # 좌표 한 글자 변조
+    content = qv_file.read_text()
+    flipped = content.replace("27.526", "27.527", 1)
+    qv_file.write_text(flipped)
+    problems = qvverify(str(qv_file))
+    assert [(p["tag"], p["status"]) for p in problems] == [("test_0", "mismatch")]
+
+    # 마지막 레코드 잘림
+    qv_file.write_text(content[:-20])
+    problems = qvverify(str(qv_file))
+    assert [(p["tag"], p["status"]) for p in problems] == [("extra", "truncated")]
+    assert problems[0]["offset"] == content.index("QV_TAG extra")
+
+def test_qvrepair():
+    """qvrepair 손상 파일 복구 테스트"""
+    atom = "ATOM      1  N   ALA A   1      27.526  24.362   4.697  1.00 20.00\n"
+    damaged = TEST_DATA_DIR / "damaged.qv"
+    damaged.write_text(
+        "QV_TAG a\n" + "QV_SCORE a ddg=-1\n" + atom
+        + "QV_TAG b\n" + "QV_SCORE a ddg=-2\n" + atom
+        + "QV_TAG a\n" + atom
+        + "QV_TAG c\n" + atom[:30]
+    )
+    repaired = TEST_DATA_DIR / "repaired.qv"
+
+    report = qvrepair(str(damaged), str(repaired))
+    assert report["kept"] == 2
+    assert [(d["tag"], d["reason"]) for d in report["dropped"]] == [
+        ("a", "duplicate tag"),
+        ("c", "truncated"),
+    ]
+    assert len(report["orphan_annotations"]) == 1
+    qv = Quiver(str(repaired), "r")
+    assert qv.get_tags() == ["a", "b"]
+    assert qv.get_scores("b") == {}
+
+    report = qvrepair(str(damaged), str(repaired), rename_duplicates=True)
+    assert Quiver(str(repaired), "r").get_tags() == ["a", "b", "a_dup1"]
+
+def _locked_writer(args):
+    qv_path, worker, n = args
+    qv = Quiver(qv_path, "w", lock=True, header=True)
+    atom = "ATOM      1  N   ALA A   1      27.526  24.362   4.697  1.00 20.00"
+    for i in range(n):
+        qv.add_pdb([atom] * 20, f"w{worker}_{i}")
+        # 다른 프로세스와 같은 태그는 거부되어야 함
+        try:
+            qv.add_pdb([atom], "shared")
+        except OSError:
+            pass
+
+def test_concurrent_locked_writers():
+    """여러 프로세스의 잠금 쓰기 테스트"""
+    import multiprocessing
+
+    qv_file = TEST_DATA_DIR / "concurrent.qv"
+    with multiprocessing.Pool(4) as pool:
+        pool.map(_locked_writer, [(str(qv_file), w, 25) for w in range(4)])
+
+    tags = Quiver(str(qv_file), "r").get_tags()
+    assert len(tags) == 4 * 25 + 1
+    assert len(set(tags)) == len(tags)
+    assert qv_file.read_text().count("QV_HEADER format_version") == 1
+
+def test_quiver_writer():
+    """QuiverWriter 버퍼 쓰기 및 컨텍스트 매니저 테스트"""
+    qv_file = TEST_DATA_DIR / "writer.qv"
+    pdb_lines = TEST_PDB_FILES[0].read_text().splitlines()
+
+    with QuiverWriter(str(qv_file), metadata={"project": "binders"}, checksum="crc32", fsync=True) as writer:
+        writer.add("w0", pdb_lines, {"ddg": -3.5})
+        added = writer.add_many((f"w{i}", pdb_lines) for i in range(1, 4))
+        assert added == 3
+        with pytest.raises(IOError):
+            writer.add("w0", pdb_lines)
+    assert writer.closed
+    with pytest.raises(IOError):
+        writer.add("w9", pdb_lines)
+
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_tags() == ["w0", "w1", "w2", "w3"]
+    assert qv.metadata["project"] == "binders"
+    assert qv.get_scores("w0") == {"ddg": -3.5}
+    assert qv.get_pdblines("w2") == [l + "\n" for l in pdb_lines]
+    assert qvverify(str(qv_file)) == []
+
+    # 기존 파일에 이어 쓰기 시 헤더는 한 번만
+    with QuiverWriter(str(qv_file)) as writer:
+        writer.add("w4", pdb_lines)
+    assert qv_file.read_text().count("QV_HEADER format_version") == 1
+    assert Quiver(str(qv_file), "r").size() == 5
+
+def _backbone_lines(chain, start, n, offset=(0.0, 0.0, 0.0), gap_after=None):
+    """직선 형태의 백본(N, CA, C, O) PDB 줄 생성"""
+    lines = []
+    x = 0.0
+    for i in range(n):
+        if gap_after is not None and i == gap_after + 1:
+            x += 10.0
+        for name, dx in (("N", 0.0), ("CA", 1.45), ("C", 2.45), ("O", 2.45)):
+            y = 1.2 if name == "O" else 0.0
+            lines.append(
+                f"ATOM  {len(lines) + 1:5d}  {name:<3s} ALA {chain}{start + i:4d}    "
+                f"{x + dx + offset[0]:8.3f}{y + offset[1]:8.3f}{offset[2]:8.3f}  1.00{80.0 + i:6.2f}"
+            )
+        x += 3.8
+    return lines
+
+def _backbone_from_torsions(torsions, chain="A"):
+    """φ/ψ 각도로부터 이상적인 백본(N, CA, C, O) PDB 줄 생성 (NeRF)"""
+    def place(a, b, c, bond, angle, torsion):
+        angle, torsion = math.radians(angle), math.radians(torsion)
+        bc = [c[i] - b[i] for i in range(3)]
+        norm = math.sqrt(sum(x * x for x in bc))
+        bc = [x / norm for x in bc]
+        ab = [b[i] - a[i] for i in range(3)]
+        n = [ab[1] * bc[2] - ab[2] * bc[1], ab[2] * bc[0] - ab[0] * bc[2], ab[0] * bc[1] - ab[1] * bc[0]]
+        norm = math.sqrt(sum(x * x for x in n))
+        n = [x / norm for x in n]
+        m = [n[1] * bc[2] - n[2] * bc[1], n[2] * bc[0] - n[0] * bc[2], n[0] * bc[1] - n[1] * bc[0]]
+        d = [-bond * math.cos(angle), bond * math.sin(angle) * math.cos(torsion), bond * math.sin(angle) * math.sin(torsion)]
+        return [c[i] + d[0] * bc[i] + d[1] * m[i] + d[2] * n[i] for i in range(3)]
+
+    n, ca, c = [0.0, 1.458, 0.0], [0.0, 0.0, 0.0], [1.525, 0.0, 0.0]
+    lines = []
+    for i, (phi, psi) in enumerate(torsions):
+        if i > 0:
+            n = place(n_prev, ca_prev, c_prev, 1.329, 116.2, psi_prev)
+            ca = place(ca_prev, c_prev, n, 1.458, 121.7, 180.0)
+            c = place(c_prev, n, ca, 1.525, 111.2, phi)
+        n_prev, ca_prev, c_prev, psi_prev = n, ca, c, psi
+        o = place(n, ca, c, 1.231, 120.5, psi + 180.0)
+        for name, xyz in (("N", n), ("CA", ca), ("C", c), ("O", o)):
+            lines.append(
+                f"ATOM  {len(lines) + 1:5d}  {name:<3s} ALA {chain}{i + 1:4d}    "
+                f"{xyz[0]:8.3f}{xyz[1]:8.3f}{xyz[2]:8.3f}  1.00 80.00"
+            )
+    return lines
+
+def test_qvstats():
+    """구조 통계 계산 및 QV_SCORE 추가 테스트"""
+    qv_file = TEST_DATA_DIR / "stats.qv"
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(_backbone_lines("A", 1, 5), "ok", {"ddg": -1.0})
+    broken = _backbone_lines("A", 1, 4, gap_after=1) + _backbone_lines("B", 1, 3, offset=(0.0, 20.0, 0.0))
+    broken = [l for l in broken if not (l[12:16].strip() == "O" and l[22:26].strip() == "3" and l[21] == "A")]
+    qv.add_pdb(broken, "broken")
+
+    table = qvstats(str(qv_file), return_table=True)
+    assert table["tag"] == ["ok", "broken"]
+    assert table["n_residues"] == [5.0, 7.0]
+    assert table["n_chains"] == [1.0, 2.0]
+    assert table["chain_lengths"] == ["A:5", "A:4,B:3"]
+    assert table["n_atoms"] == [20.0, 27.0]
+    assert table["missing_backbone"] == [0.0, 1.0]
+    assert table["chain_breaks"] == [0.0, 1.0]
+    assert table["mean_b_factor"][0] == pytest.approx(82.0)
+    assert table["radius_of_gyration"][0] > 0
+
+    out = TEST_DATA_DIR / "stats.csv"
+    qvstats(str(qv_file), output=str(out))
+    assert out.read_text().splitlines()[0].startswith("tag,n_residues,n_chains,chain_lengths")
+
+    qvstats(str(qv_file), append_scores=True)
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_scores("ok")["ddg"] == -1.0
+    assert qv.get_scores("ok")["n_residues"] == 5.0
+    assert qv.get_scores("broken")["chain_breaks"] == 1.0
+    assert qv.get_pdblines("ok")[0].startswith("ATOM")
+
+    # 점수는 새 파일로 바꿔치기되므로 열려 있던 읽기 핸들은 예전 내용을 그대로 읽음
+    ok_lines = qv.get_pdblines("ok")
+    assert not (TEST_DATA_DIR / "stats.qv.scores.tmp").exists()
+    writer = Quiver(str(qv_file), "w", lock=True)
+    qvstats(str(qv_file), append_scores=True)
+    assert qv.get_pdblines("ok") == ok_lines
+    assert qv.get_pdblines("broken")[0].startswith("ATOM")
+
+    # 이후 잠금 쓰기는 다시 써진 파일에 이어짐
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_pdblines("ok") == ok_lines
+    writer.add_pdb(qv.get_pdblines("ok"), "after")
+    qv = Quiver(str(qv_file), "r")
+    assert qv.get_tags()[-1] == "after"
+    assert qv.get_pdblines("after") == ok_lines
+    assert qv.get_scores("ok")["n_residues"] == 5.0
+
+def _moved_lines(lines, shift=(0.0, 0.0, 0.0), jitter=0.0):
+    """z축 90도 회전 + 평행이동 (선택적으로 원자마다 흔들기)"""
+    moved = []
+    for i, line in enumerate(lines):
+        x, y, z = float(line[30:38]), float(line[38:46]), float(line[46:54])
+        x, y = -y, x
+        dz = jitter if i % 2 else -jitter
+        moved.append(f"{line[:30]}{x + shift[0]:8.3f}{y + shift[1]:8.3f}{z + shift[2] + dz:8.3f}{line[54:]}")
+    return moved
+
+def test_qvdssp():
+    """DSSP 방식 2차 구조 할당 테스트"""
+    qv_file = TEST_DATA_DIR / "dssp.qv"
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(_backbone_from_torsions([(-57, -47)] * 20), "helix")
+    qv.add_pdb(_backbone_from_torsions([(-120, 120)] * 6 + [(60, -120), (-80, 0)] + [(-120, 120)] * 6), "hairpin")
+    qv.add_pdb(_backbone_from_torsions([(-120, 130)] * 12), "extended")
+
+    table = qvdssp(str(qv_file), return_table=True)
+    assert table["tag"] == ["helix", "hairpin", "extended"]
+    assert table["dssp"] == ["-" + "H" * 18 + "-", "-EEEEETTEEEEE-", "-" * 12]
+    assert table["helix"] == [0.9, 0.0, 0.0]
+    assert table["strand"][1] == pytest.approx(10 / 14, abs=1e-3)
+    assert table["loop"][2] == 1.0
+
+    qvdssp(str(qv_file))
+    assert (TEST_DATA_DIR / "dssp.dssp.sc").read_text().startswith("tag\tdssp\thelix\tstrand\tloop")
+
+    qvdssp(str(qv_file), append_scores=True)
+    content = qv_file.read_text()
+    assert "QV_SCORE helix dssp=-HHHHHHHHHHHHHHHHHH-|helix=0.900|strand=0.000|loop=0.100" in content
+
+def test_qvinterface():
+    """체인 간 접촉/계면 잔기/충돌 분석 테스트"""
+    qv_file = TEST_DATA_DIR / "interface.qv"
+    target = _backbone_lines("A", 1, 8)
+    binder = _backbone_lines("B", 1, 3, offset=(7.6, 4.0, 0.0))
+    far = _backbone_lines("C", 1, 3, offset=(0.0, 50.0, 0.0))
+    clashing = _backbone_lines("B", 1, 3, offset=(7.6, 1.0, 0.0))
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(target + binder + far, "bound")
+    qv.add_pdb(target + clashing, "clash")
+    qv.add_pdb(target, "monomer")
+
+    table = qvinterface(str(qv_file), return_table=True)
+    assert table["tag"] == ["bound", "clash", "monomer"]
+
+    # 격자 탐색 결과를 전수 비교와 대조
+    coords = [(l[21], float(l[30:38]), float(l[38:46]), float(l[46:54])) for l in target + binder]
+    expected = sum(
+        1
+        for i, a in enumerate(coords)
+        for b in coords[i + 1:]
+        if a[0] != b[0] and math.dist(a[1:], b[1:]) <= 5.0
+    )
+    assert table["atom_contacts"][0] == expected > 0
+    assert table["clashes"][0] == 0
+    assert table["clashes"][1] > 0
+    assert table["interface_residues"][0].startswith("A:") and ";B:1,2,3" in table["interface_residues"][0]
+    assert table["contacts"][2] == 0 and table["interface_residues"][2] == ""
+
+    table = qvinterface(str(qv_file), chains="A:C", return_table=True)
+    assert table["atom_contacts"][0] == 0
+
+    qvinterface(str(qv_file), chains="A:B", append_scores=True)
+    assert "n_interface_residues=" in qv_file.read_text()
+
+    with pytest.raises(ValueError):
+        qvinterface(str(qv_file), chains="AB")
+    with pytest.raises(ValueError):
+        qvinterface(str(qv_file), chains="A:A")
+
+def test_rmsd():
+    """Kabsch 중첩 RMSD 테스트"""
+    qv_file = TEST_DATA_DIR / "rmsd.qv"
+    ref = _backbone_lines("A", 1, 6)
+    ref = [l[:38] + f"{float(l[38:46]) + 0.7 * (i % 3):8.3f}" + l[46:] for i, l in enumerate(ref)]
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(ref, "ref")
+    qv.add_pdb(_moved_lines(ref, shift=(5.0, -3.0, 12.0)), "moved")
+    qv.add_pdb(_moved_lines(ref, jitter=0.5), "jittered")
+
+    qv = Quiver(str(qv_file), "r")
+    assert qv.rmsd("ref", "moved") == pytest.approx(0.0, abs=1e-3)
+    assert qv.rmsd("ref", "moved", atoms="backbone") == pytest.approx(0.0, abs=1e-3)
+    jittered = qv.rmsd("ref", "jittered", atoms="heavy")
+    assert 0.0 < jittered <= 0.5 + 1e-6
+    assert qv.rmsd("jittered", "ref", atoms="heavy") == pytest.approx(jittered, abs=1e-6)
+    with pytest.raises(ValueError):
+        qv.rmsd("ref", "moved", atoms="sidechain")
+
+def test_rmsd_numpy():
+    """RMSD 행렬/기준 구조 NumPy 결과 테스트"""
+    np = pytest.importorskip("numpy")
+    qv_file = TEST_DATA_DIR / "rmsd.qv"
+    tags = ["ref", "moved", "jittered"]
+
+    matrix = rmsd_matrix(str(qv_file), tags=tags, atoms="heavy")
+    assert matrix.shape == (3, 3)
+    assert np.allclose(matrix, matrix.T)
+    assert np.allclose(np.diag(matrix), 0.0)
+    assert matrix[0, 1] == pytest.approx(0.0, abs=1e-3)
+
+    reference = TEST_DATA_DIR / "rmsd_ref.pdb"
+    reference.write_text("\n".join(Quiver(str(qv_file), "r").get_pdblines("ref")))
+    values = rmsd_to_reference(str(qv_file), str(reference), atoms="heavy", append_scores=True)
+    assert values.shape == (3,)
+    assert np.allclose(values, matrix[0], atol=1e-3)
+    assert Quiver(str(qv_file), "r").get_scores("jittered")["rmsd"] == pytest.approx(values[2], abs=1e-3)
+
+    fit = Quiver(str(qv_file), "r").superpose("moved", "ref")
+    assert fit["rotation"].shape == (3, 3)
+    assert np.allclose(fit["rotation"] @ fit["rotation"].T, np.eye(3), atol=1e-6)
+
+def test_qvcluster():
+    """RMSD/TM-score 클러스터링 및 대표 구조 추출 테스트"""
+    qv_file = TEST_DATA_DIR / "cluster.qv"
+    base = _backbone_lines("A", 1, 12)
+    base = [l[:38] + f"{float(l[38:46]) + 1.5 * (i % 5):8.3f}" + l[46:] for i, l in enumerate(base)]
+    stretched = [l[:30] + f"{float(l[30:38]) * 2.0:8.3f}" + l[38:] for l in base]
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(base, "a", {"ddg": -1.0})
+    qv.add_pdb(stretched, "far", {"ddg": -9.0})
+    qv.add_pdb(_moved_lines(base, shift=(3.0, 1.0, -2.0)), "b", {"ddg": -2.0})
+    qv.add_pdb(_moved_lines(base, jitter=0.2), "c", {"ddg": -5.0})
+
+    for metric, threshold in (("rmsd", 1.0), ("tmscore", 0.5)):
+        for method in ("leader", "greedy"):
+            result = qvcluster(str(qv_file), threshold, metric=metric, method=method)
+            clusters = dict(zip(result["tags"], result["cluster"]))
+            assert clusters["a"] == clusters["b"] == clusters["c"], (metric, method)
+            assert clusters["far"] != clusters["a"], (metric, method)
+            assert len(result["representatives"]) == 2
+
+    result = qvcluster(str(qv_file), 1.0, score_term="ddg", output=str(TEST_DATA_DIR / "reps.qv"))
+    assert result["tags"] == ["far", "c", "b", "a"]
+    assert result["representatives"] == ["far", "c"]
+    assert Quiver(str(TEST_DATA_DIR / "reps.qv"), "r").get_tags() == ["far", "c"]
+
+    # pLDDT처럼 높을수록 좋은 점수는 내림차순
+    result = qvcluster(str(qv_file), 1.0, method="greedy", score_term="ddg", descending=True)
+    assert result["tags"] == ["a", "b", "c", "far"]
+    assert result["representatives"] == ["a", "far"]
+
+    with pytest.raises(ValueError):
+        qvcluster(str(qv_file), 1.0, metric="gdt")
+
+def test_qvselect():
+    """선택 구문(qvselect) 및 추출 시 원자 선택 테스트"""
+    qv_file = TEST_DATA_DIR / "complex.qv"
+    complex_lines = _backbone_lines("A", 1, 6) + ["TER"] + _backbone_lines("B", 1, 3, offset=(0.0, 20.0, 0.0))
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(complex_lines, "complex", {"ddg": -3.0}, checksum="crc32")
+    qv.add_pdb(_backbone_lines("B", 1, 3), "binder_only")
+
+    def atoms(path, tag):
+        lines = Quiver(str(path), "r").get_pdblines(tag)
+        return [(l[21], int(l[22:26]), l[12:16].strip()) for l in lines if l.startswith("ATOM")]
+
+    output = TEST_DATA_DIR / "chain_a.qv"
+    assert qvselect(str(qv_file), "chain A", str(output)) == ["binder_only"]
+    assert Quiver(str(output), "r").get_tags() == ["complex"]
+    assert {chain for chain, _, _ in atoms(output, "complex")} == {"A"}
+    assert "QV_SCORE complex ddg=-3.0" in output.read_text()
+    assert qvverify(str(output)) == []
+
+    qvselect(str(qv_file), "chain A and resi 2-3+5 and name CA", str(output))
+    assert atoms(output, "complex") == [("A", 2, "CA"), ("A", 3, "CA"), ("A", 5, "CA")]
+
+    qvselect(str(qv_file), "(chain B or resi 1) and not (name O or name N)", str(output))
+    assert atoms(output, "complex") == [("A", 1, "CA"), ("A", 1, "C")] + [
+        ("B", i, name) for i in (1, 2, 3) for name in ("CA", "C")
+    ]
+
+    cif_dir = TEST_DATA_DIR / "cif_select"
+    extract_mmcif(str(qv_file), str(cif_dir), select="chain B")
+    assert " A " not in (cif_dir / "complex.cif").read_text().split("_atom_site.pdbx_PDB_model_num")[1]
+    for cif_file in cif_dir.glob("*.cif"):
+        cif_file.unlink()
+    cif_dir.rmdir()
+
+    qv = Quiver(str(qv_file), "r")
+    assert qv.rmsd("complex", "complex", atoms="chain A and name CA") == pytest.approx(0.0, abs=1e-6)
+
+    for bad in ("chain", "chain A and", "resi 5-1", "name CA)", "bogus A"):
+        with pytest.raises(ValueError):
+            qvselect(str(qv_file), bad, str(output))
+
+def test_qvtransform():
+    """qvtransform 재번호/체인 변경/제거/중심 이동 테스트"""
+    qv_file = TEST_DATA_DIR / "transform.qv"
+    lines = _backbone_lines("B", 10, 3, offset=(5.0, 5.0, 5.0)) + ["TER"]
+    lines.append("ATOM     99  H   ALA B  12       5.000   6.000   5.000  1.00 80.00           H")
+    lines.append("HETATM  100  O   HOH W 201       1.000   1.000   1.000  1.00 30.00           O")
+    lines.append("HETATM  101 ZN    ZN Z 301       2.000   2.000   2.000  1.00 30.00          ZN")
+    lines.append("CONECT  101    2")
+    lines.append("ATOM    102  CB  ALA B  12        n/a     n/a     n/a  1.00 80.00           C")
+    qv = Quiver(str(qv_file), "w")
+    qv.add_pdb(lines, "model", {"ddg": -1.0}, checksum="xxh64")
+    qv.add_pdb(["HETATM    1  O   HOH W   1       0.000   0.000   0.000  1.00 30.00           O"], "water")
+
+    def atoms(path):
+        lines = Quiver(str(path), "r").get_pdblines("model")
+        return [l for l in lines if l.startswith(("ATOM", "HETATM"))]
+
+    output = TEST_DATA_DIR / "transformed.qv"
+    skipped = qvtransform(
+        str(qv_file), str(output), renumber=True, rechain={"B": "A"},
+        strip_hydrogens=True, strip_waters=True, reserial=True,
+    )
+    assert skipped == ["water"]
+    result = atoms(output)
+    assert [l[12:16].strip() for l in result][-1] == "ZN"
+    assert [int(l[6:11]) for l in result] == list(range(1, 14))
+    assert [l[21] for l in result[:12]] == ["A"] * 12
+    assert [int(l[22:26]) for l in result[:12:4]] == [1, 2, 3]
+    assert "QV_SCORE model ddg=-1.0" in output.read_text()
+    assert qvverify(str(output)) == []
+
+    qvtransform(str(qv_file), str(output), recenter=True)
+    result = atoms(output)
+    assert result[-1][30:54].split() == ["n/a"] * 3
+    coords = [(float(l[30:38]), float(l[38:46]), float(l[46:54])) for l in result[:-1]]
+    assert len(coords) == 15
+    for axis in range(3):
+        assert abs(sum(c[axis] for c in coords)) < 0.01
+
+    with pytest.raises(ValueError):
+        qvtransform(str(qv_file), str(output))
+    with pytest.raises(ValueError):
+        qvtransform(str(qv_file), str(output), rechain={"AB": "C"})
+
+    # 고정 열에 들어가지 않는 좌표는 조용히 남기지 않고 오류
+    far = TEST_DATA_DIR / "far.qv"
+    far_qv = Quiver(str(far), "w")
+    far_qv.add_pdb([
+        "ATOM      1  CA  ALA A   1       0.000   0.000   0.000  1.00 80.00           C",
+        "ATOM      2  CA  ALA A   2    2400.000   0.000   0.000  1.00 80.00           C",
+    ], "far")
+    with pytest.raises(ValueError, match="far: x coordinate '-1200.000' does not fit"):
+        qvtransform(str(far), str(output), recenter=True)
+
 def test_list_tags():
     """list_tags 도구 테스트"""
     start_time = time.time()
@@ -107,6 +782,30 @@ def test_rename_tags():
     list_tags(str(TEST_QV_FILE))  # 함수는 None을 반환하므로 반환값 검사 제거
     assert end_time - start_time < 1.0  # 1초 이내 실행
 
+def test_rename_tags_modes():
+    """rename_tags 매핑/정규식/접두사 모드 테스트"""
+    renamed = TEST_DATA_DIR / "renamed.qv"
+    renamed.write_text(TEST_QV_FILE.read_text())
+
+    def tags():
+        return [l.split()[1] for l in renamed.read_text().splitlines() if l.startswith("QV_TAG")]
+
+    rename_tags(str(renamed), mapping={"new_tag2": "middle"})
+    assert tags() == ["new_tag1", "middle", "new_tag3"]
+    assert "QV_SCORE middle rms=1.5|score=0.8" in renamed.read_text()
+
+    rename_tags(str(renamed), pattern=r"^new_", replacement="")
+    assert tags() == ["tag1", "middle", "tag3"]
+
+    rename_tags(str(renamed), prefix="run2_")
+    assert tags() == ["run2_tag1", "run2_middle", "run2_tag3"]
+
+    # 충돌은 파일을 건드리기 전에 거부
+    before = renamed.read_text()
+    with pytest.raises(ValueError):
+        rename_tags(str(renamed), mapping={"run2_tag1": "run2_tag3"})
+    assert renamed.read_text() == before
+
 def test_qvslice():
     """qvslice 도구 테스트"""
     start_time = time.time()
@@ -116,6 +815,21 @@ def test_qvslice():
 
     assert end_time - start_time < 1.0  # 1초 이내 실행
 
+def test_qvslice_patterns():
+    """qvslice 패턴 매칭 및 순서 지정 테스트"""
+    output = TEST_DATA_DIR / "sliced.qv"
+    qvslice(str(TEST_QV_FILE), ["new_tag3", "new_tag1"], ordered=True, output=str(output))
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag3", "new_tag1"]
+
+    qvslice(str(TEST_QV_FILE), ["new_tag[12]"], match_mode="glob", output=str(output))
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag1", "new_tag2"]
+
+    qvslice(str(TEST_QV_FILE), ["2$"], match_mode="regex", invert=True, output=str(output))
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag1", "new_tag3"]
+
 def test_qvsplit():
     """qvsplit 도구 테스트"""
     start_time = time.time()
@@ -129,6 +843,165 @@ def test_qvsplit():
     assert len(split_files) > 0
     assert end_time - start_time < 2.0  # 2초 이내 실행
 
+def test_qvsample():
+    """qvsample 도구 테스트"""
+    output = TEST_DATA_DIR / "sample.qv"
+    qvsample(str(TEST_QV_FILE), str(output), n=2, seed=7)
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert len(tags) == 2
+    assert tags == sorted(tags)
+
+    # 같은 시드는 같은 결과
+    first = output.read_text()
+    qvsample(str(TEST_QV_FILE), str(output), n=2, seed=7)
+    assert output.read_text() == first
+
+    # 태그 그룹별 1개씩
+    qvsample(str(TEST_QV_FILE), str(output), n=1, group_regex=r"tag(\d)")
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag1", "new_tag2", "new_tag3"]
+
+    # 매치되지 않은 태그는 빈 캡처와 다른 그룹
+    qvsample(str(TEST_QV_FILE), str(output), n=1, group_regex=r"tag1(\d*)")
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags[0] == "new_tag1"
+    assert len(tags) == 2
+
+    # 점수 구간별 1개씩
+    qvsample(str(TEST_QV_FILE), str(output), n=1, score_term="score", bin_width=0.1)
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert len(tags) == 1
+
+    qvsample(str(TEST_QV_FILE), str(output), fraction=1.0)
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag1", "new_tag2", "new_tag3"]
+
+    # 비율은 그룹마다 round(fraction * n)개씩 정확히 뽑음
+    grouped = TEST_DATA_DIR / "grouped.qv"
+    atom = "ATOM      1  N   ALA A   1      27.526  24.362   4.697  1.00 20.00\n"
+    grouped.write_text("".join(f"QV_TAG {g}_{i}\n{atom}" for g, n in (("a", 4), ("b", 6), ("c", 3)) for i in range(n)))
+    for seed in range(5):
+        qvsample(str(grouped), str(output), fraction=0.5, seed=seed, group_regex=r"^(\w)_")
+        tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+        counts = {g: sum(t.startswith(g + "_") for t in tags) for g in "abc"}
+        assert counts == {"a": 2, "b": 3, "c": 2}
+
+def test_qvsort():
+    """qvsort 도구 테스트"""
+    output = TEST_DATA_DIR / "sorted.qv"
+    qvsort(str(TEST_QV_FILE), str(output), reverse=True)
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag3", "new_tag2", "new_tag1"]
+
+def test_qvdedup():
+    """qvdedup 도구 테스트"""
+    duplicated = TEST_DATA_DIR / "duplicated.qv"
+    duplicated.write_text(TEST_QV_FILE.read_text() * 2)
+    output = TEST_DATA_DIR / "dedup.qv"
+
+    dropped = qvdedup(str(duplicated), str(output))
+    assert dropped == ["new_tag1", "new_tag2", "new_tag3"]
+
+    # 테스트 PDB들은 좌표가 모두 같음
+    dropped = qvdedup(str(duplicated), str(output), by_structure=True)
+    assert len(dropped) == 5
+    tags = [l.split()[1] for l in output.read_text().splitlines() if l.startswith("QV_TAG")]
+    assert tags == ["new_tag1"]
+
+    # 원자가 없는 레코드는 구조 중복으로 보지 않음
+    empty = TEST_DATA_DIR / "empty_records.qv"
+    empty.write_text("QV_TAG a\nREMARK a\nQV_TAG b\nREMARK b\n")
+    assert qvdedup(str(empty), str(output), by_structure=True) == []
+
+def test_qvdiff():
+    """두 Quiver 파일 비교(qvdiff) 테스트"""
+    lines = _backbone_lines("A", 1, 4)
+    file_a = TEST_DATA_DIR / "diff_a.qv"
+    file_b = TEST_DATA_DIR / "diff_b.qv"
+    qv = Quiver(str(file_a), "w")
+    qv.add_pdb(lines, "same", {"ddg": 1.5})
+    qv.add_pdb(lines, "moved", {"ddg": -1.0, "rms": 0.2})
+    qv.add_pdb(lines, "only_a")
+    qv = Quiver(str(file_b), "w")
+    qv.add_pdb([l + "\r" for l in lines] + ["END"], "same", {"ddg": "1.50"})
+    shifted = [f"{l[:46]}{float(l[46:54]) + 0.001:8.3f}{l[54:]}" for l in lines]
+    qv.add_pdb(shifted, "moved", {"ddg": -2.0, "sap": 3.0})
+    qv.add_pdb(lines, "only_b")
+
+    result = qvdiff(str(file_a), str(file_b))
+    assert not result["identical"]
+    assert result["only_a"] == ["only_a"]
+    assert result["only_b"] == ["only_b"]
+    assert [tag for tag, _ in result["structures"]] == ["moved"]
+    assert result["structures"][0][1] == pytest.approx(0.001, abs=1e-6)
+    assert sorted(result["scores"]) == [
+        ("moved", "ddg", "-1.0", "-2.0"),
+        ("moved", "rms", "0.2", None),
+        ("moved", "sap", None, "3.0"),
+    ]
+
+    result = qvdiff(str(file_a), str(file_b), rmsd_tolerance=0.01, scores=False)
+    assert result["structures"] == [] and result["scores"] == []
+    assert qvdiff(str(file_a), str(file_a))["identical"]
+
+def _arrow_qv():
+    """Arrow 변환 테스트용 Quiver 파일 생성"""
+    qv_file = TEST_DATA_DIR / "arrow.qv"
+    qv = Quiver(str(qv_file), "w")
+    complex_lines = _backbone_lines("A", 1, 3) + ["TER"] + _backbone_lines("B", 7, 2, offset=(0.0, 20.0, 0.0))
+    qv.add_pdb(complex_lines, "complex", {"ddg": -3.0, "note": "ok"})
+    qv.add_pdb(_backbone_lines("A", 1, 2), "single", {"ddg": -1.0})
+    return qv_file
+
+def test_to_arrow():
+    """Arrow 스트림 및 Parquet 일괄 변환 테스트"""
+    qv_file = _arrow_qv()
+
+    parquet_file = TEST_DATA_DIR / "arrow.parquet"
+    assert to_arrow(str(qv_file), parquet=str(parquet_file)) is None
+    data = parquet_file.read_bytes()
+    assert data[:4] == b"PAR1" and data[-4:] == b"PAR1"
+
+    stream = to_arrow(str(qv_file), tags=["single"], select="name CA")
+    assert type(stream.__arrow_c_schema__()).__name__ == "PyCapsule"
+    assert type(stream.__arrow_c_stream__()).__name__ == "PyCapsule"
+    with pytest.raises(ValueError):
+        stream.__arrow_c_stream__()
+
+    with pytest.raises(ValueError):
+        to_arrow(str(qv_file), tags=["missing"])
+    with pytest.raises(ValueError):
+        to_arrow(str(qv_file), batch_size=0)
+
+def test_to_arrow_pyarrow():
+    """pyarrow로 읽은 Arrow/Parquet 테이블 내용 테스트"""
+    pa = pytest.importorskip("pyarrow")
+    pq = pytest.importorskip("pyarrow.parquet")
+    qv_file = _arrow_qv()
+
+    table = pa.table(to_arrow(str(qv_file), batch_size=1))
+    assert table.column_names == [
+        "tag", "scores", "sequence", "atom_name", "res_name", "chain",
+        "res_seq", "residue_index", "element", "coords", "b_factor",
+    ]
+    rows = table.to_pylist()
+    assert [row["tag"] for row in rows] == ["complex", "single"]
+    assert rows[0]["scores"] == {"ddg": -3.0, "note": "ok"}
+    assert rows[1]["scores"] == {"ddg": -1.0, "note": None}
+    assert rows[0]["sequence"] == "AAA/AA"
+    assert rows[0]["chain"][-1] == "B" and rows[0]["res_seq"][-1] == 8
+    assert rows[0]["residue_index"][-1] == 4
+    assert rows[0]["coords"][1] == pytest.approx([1.45, 0.0, 0.0])
+
+    selected = pa.table(to_arrow(str(qv_file), tags=["complex"], select="chain B and name CA"))
+    assert selected.num_rows == 1
+    assert selected.column("atom_name").to_pylist() == [["CA", "CA"]]
+    assert selected.column("sequence").to_pylist() == ["AA"]
+
+    parquet_file = TEST_DATA_DIR / "arrow.parquet"
+    to_arrow(str(qv_file), parquet=str(parquet_file))
+    assert pq.read_table(parquet_file).to_pylist() == rows
+
 def test_performance_large_file():
     """대용량 파일 처리 성능 테스트"""
     # 대용량 테스트 파일 생성